@@ -0,0 +1,403 @@
+//! Absolute addresses.
+//! This module computes the absolute address of registers and finds registers by address
+
+use crate::path;
+use crate::svd::{
+    dimelement::{expand_name, DimElement},
+    Cluster, ClusterInfo, Device, Field, Peripheral, Register, RegisterInfo, RegisterProperties,
+};
+
+/// Register instance located at an absolute address
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterAddress<'a> {
+    /// Peripheral containing the register
+    pub peripheral: &'a Peripheral,
+
+    /// Clusters enclosing the register, outermost first
+    pub clusters: Vec<&'a ClusterInfo>,
+
+    /// Register description, shared by all elements of a register array
+    pub register: &'a RegisterInfo,
+
+    /// Names of the peripheral, clusters and register,
+    /// with the `%s` placeholders of arrays substituted
+    pub path: Vec<String>,
+
+    /// Absolute address of the register
+    pub address: u64,
+
+    /// Register properties inherited from the device, peripheral and clusters
+    pub properties: RegisterProperties,
+}
+
+impl<'a> RegisterAddress<'a> {
+    /// Returns the dotted path of the register, e.g. `PERIPH.CLUSTER.REG`
+    pub fn path_name(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Returns the fields of the register with field arrays expanded
+    pub fn fields(&self) -> Vec<Field> {
+        self.register
+            .fields
+            .iter()
+            .flatten()
+            .flat_map(Field::expand)
+            .collect()
+    }
+
+    /// Checks if `address` belongs to the register.
+    ///
+    /// `address_unit_bits` is the number of bits selected by each address,
+    /// 0 is taken as 8. Registers with unknown size only contain their own address
+    pub fn contains(&self, address: u64, address_unit_bits: u32) -> bool {
+        address >= self.address && address - self.address < self.units(address_unit_bits)
+    }
+
+    /// Returns the number of addresses taken by the register
+    fn units(&self, address_unit_bits: u32) -> u64 {
+        let address_unit_bits = if address_unit_bits == 0 {
+            8
+        } else {
+            address_unit_bits
+        };
+        let units = match self.properties.size {
+            Some(size) if size > address_unit_bits => {
+                size / address_unit_bits + u32::from(size % address_unit_bits != 0)
+            }
+            _ => 1,
+        };
+        u64::from(units)
+    }
+}
+
+/// Registers of a device sorted by absolute address.
+///
+/// Built once by [`AddressMap::new`], it finds the registers containing
+/// an address with a binary search. Derived peripherals without registers
+/// of their own are skipped unless [`Device::resolve_derivations`] was called first
+#[derive(Clone, Debug)]
+pub struct AddressMap<'a> {
+    registers: Vec<RegisterAddress<'a>>,
+    address_unit_bits: u32,
+    /// Largest number of addresses taken by a register
+    max_units: u64,
+}
+
+impl<'a> AddressMap<'a> {
+    /// Computes the addresses of all registers of `device`, see [`register_addresses`]
+    pub fn new(device: &'a Device) -> Self {
+        let address_unit_bits = device.address_unit_bits.unwrap_or(8);
+        let mut registers = register_addresses(device);
+        registers.sort_by_key(|r| r.address);
+        let max_units = registers
+            .iter()
+            .map(|r| r.units(address_unit_bits))
+            .max()
+            .unwrap_or(1);
+        Self {
+            registers,
+            address_unit_bits,
+            max_units,
+        }
+    }
+
+    /// Returns all registers sorted by address
+    pub fn registers(&self) -> &[RegisterAddress<'a>] {
+        &self.registers
+    }
+
+    /// Returns registers which contain `address`.
+    ///
+    /// More than one register is returned when registers overlap,
+    /// e.g. alternate registers
+    pub fn find(&self, address: u64) -> impl Iterator<Item = &RegisterAddress<'a>> {
+        // Only registers starting less than `max_units` before `address` can contain it
+        let lowest = address.saturating_sub(self.max_units - 1);
+        let start = self.registers.partition_point(|r| r.address < lowest);
+        let end = self.registers.partition_point(|r| r.address <= address);
+        let address_unit_bits = self.address_unit_bits;
+        self.registers[start..end]
+            .iter()
+            .filter(move |r| r.contains(address, address_unit_bits))
+    }
+}
+
+/// Returns names and offsets of the elements described by a possibly dimensioned element.
+/// Stops at the first element whose offset overflows
+fn instances(name: &str, offset: u64, dim: Option<&DimElement>) -> Vec<(String, u64)> {
+    match dim {
+        None => vec![(name.to_string(), offset)],
+        Some(dim) => dim
+            .indexes()
+            .enumerate()
+            .map_while(|(i, index)| {
+                let increment = u64::from(dim.dim_increment).checked_mul(i as u64)?;
+                let offset = offset.checked_add(increment)?;
+                Some((expand_name(name, &index), offset))
+            })
+            .collect(),
+    }
+}
+
+/// Returns every register of `device` with its absolute address.
+///
+/// Peripheral, register and cluster arrays produce one entry per element,
+/// registers of each peripheral are sorted by address. Elements whose address
+/// overflows are left out. Derived peripherals without registers of their own
+/// are skipped unless [`Device::resolve_derivations`] was called first
+pub fn register_addresses(device: &Device) -> Vec<RegisterAddress<'_>> {
+    let mut out = Vec::new();
+    for p in &device.peripherals {
+        let registers: Vec<_> = path::peripheral_reg_path_iter(device, p).collect();
+        let dim = match p {
+            Peripheral::Single(_) => None,
+            Peripheral::Array(_, dim) => Some(dim),
+        };
+        for (name, base) in instances(&p.name, p.base_address, dim) {
+            let start = out.len();
+            for r in &registers {
+                // Names and addresses of the enclosing cluster instances
+                let mut prefixes = vec![(vec![name.clone()], base)];
+                for c in &r.clusters {
+                    let dim = match c {
+                        Cluster::Single(_) => None,
+                        Cluster::Array(_, dim) => Some(dim),
+                    };
+                    let cluster_instances = instances(&c.name, 0, dim);
+                    prefixes = prefixes
+                        .into_iter()
+                        .flat_map(|(path, address)| {
+                            cluster_instances
+                                .iter()
+                                .filter_map(move |(name, offset)| {
+                                    let mut path = path.clone();
+                                    path.push(name.clone());
+                                    Some((path, address.checked_add(*offset)?))
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .collect();
+                }
+                let dim = match r.register {
+                    Register::Single(_) => None,
+                    Register::Array(_, dim) => Some(dim),
+                };
+                for (mut path, address) in prefixes {
+                    let address = match address.checked_add(r.offset) {
+                        Some(address) => address,
+                        None => continue,
+                    };
+                    for (name, address) in instances(&r.register.name, address, dim) {
+                        path.push(name);
+                        out.push(RegisterAddress {
+                            peripheral: p,
+                            clusters: r.clusters.iter().map(|c| &***c).collect(),
+                            register: r.register,
+                            path: path.clone(),
+                            address,
+                            properties: r.properties,
+                        });
+                        path.pop();
+                    }
+                }
+            }
+            out[start..].sort_by_key(|r| r.address);
+        }
+    }
+    out
+}
+
+/// Returns registers of `device` which contain `address`.
+///
+/// More than one register is returned when registers overlap,
+/// e.g. alternate registers. Derived peripherals without registers of their own
+/// are skipped unless [`Device::resolve_derivations`] was called first.
+/// Build an [`AddressMap`] once to look up many addresses
+pub fn find_by_address(device: &Device, address: u64) -> Vec<RegisterAddress<'_>> {
+    AddressMap::new(device).find(address).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn addresses() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>GPIOA</name>
+                        <baseAddress>0x40020000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>MODER</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <cluster>
+                                <name>CH%s</name>
+                                <addressOffset>0x10</addressOffset>
+                                <dim>2</dim>
+                                <dimIncrement>0x10</dimIncrement>
+                                <register>
+                                    <name>CR</name>
+                                    <addressOffset>0x0</addressOffset>
+                                    <fields>
+                                        <field>
+                                            <name>EN</name>
+                                            <bitOffset>0</bitOffset>
+                                            <bitWidth>1</bitWidth>
+                                        </field>
+                                    </fields>
+                                </register>
+                                <register>
+                                    <name>DATA[%s]</name>
+                                    <addressOffset>0x4</addressOffset>
+                                    <size>16</size>
+                                    <dim>2</dim>
+                                    <dimIncrement>0x2</dimIncrement>
+                                </register>
+                            </cluster>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let regs: Vec<_> = device
+            .register_addresses()
+            .iter()
+            .map(|r| (r.path_name(), r.address))
+            .collect();
+        assert_eq!(
+            regs,
+            vec![
+                ("GPIOA.MODER".to_string(), 0x40020000),
+                ("GPIOA.CH0.CR".to_string(), 0x40020010),
+                ("GPIOA.CH0.DATA0".to_string(), 0x40020014),
+                ("GPIOA.CH0.DATA1".to_string(), 0x40020016),
+                ("GPIOA.CH1.CR".to_string(), 0x40020020),
+                ("GPIOA.CH1.DATA0".to_string(), 0x40020024),
+                ("GPIOA.CH1.DATA1".to_string(), 0x40020026),
+            ]
+        );
+
+        let found = device.find_by_address(0x4002_0022);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path_name(), "GPIOA.CH1.CR");
+        assert_eq!(found[0].clusters[0].name, "CH%s");
+        assert_eq!(found[0].fields()[0].name, "EN");
+
+        let found = device.find_by_address(0x4002_0027);
+        assert_eq!(found[0].path_name(), "GPIOA.CH1.DATA1");
+
+        assert!(device.find_by_address(0x4002_0028).is_empty());
+        assert!(found[0].contains(0x4002_0027, 0));
+    }
+
+    #[test]
+    fn overflow() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>TOP</name>
+                        <baseAddress>0xFFFFFFFFFFFFFFF8</baseAddress>
+                        <addressBlock>
+                            <offset>0x0</offset>
+                            <size>0x10</size>
+                            <usage>registers</usage>
+                        </addressBlock>
+                        <registers>
+                            <register>
+                                <name>DATA%s</name>
+                                <addressOffset>0x0</addressOffset>
+                                <dim>4</dim>
+                                <dimIncrement>0x4</dimIncrement>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let regs: Vec<_> = device
+            .register_addresses()
+            .iter()
+            .map(|r| (r.path_name(), r.address))
+            .collect();
+        assert_eq!(
+            regs,
+            vec![
+                ("TOP.DATA0".to_string(), 0xFFFF_FFFF_FFFF_FFF8),
+                ("TOP.DATA1".to_string(), 0xFFFF_FFFF_FFFF_FFFC),
+            ]
+        );
+    }
+
+    #[test]
+    fn address_map() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>UART0</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>DATA</name>
+                                <addressOffset>0x0</addressOffset>
+                                <size>64</size>
+                            </register>
+                            <register>
+                                <name>RX</name>
+                                <alternateRegister>DATA</alternateRegister>
+                                <addressOffset>0x0</addressOffset>
+                                <size>8</size>
+                            </register>
+                            <register>
+                                <name>SR</name>
+                                <addressOffset>0x8</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                    <peripheral derivedFrom=\"UART0\">
+                        <name>UART1</name>
+                        <baseAddress>0x40001000</baseAddress>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let map = device.address_map();
+        let found: Vec<_> = map.find(0x4000_0005).map(|r| r.path_name()).collect();
+        assert_eq!(found, vec!["UART0.DATA"]);
+        let found: Vec<_> = map.find(0x4000_0000).map(|r| r.path_name()).collect();
+        assert_eq!(found, vec!["UART0.DATA", "UART0.RX"]);
+        let found: Vec<_> = map.find(0x4000_000B).map(|r| r.path_name()).collect();
+        assert_eq!(found, vec!["UART0.SR"]);
+        assert_eq!(map.find(0x4000_000C).count(), 0);
+        // derived peripherals get their registers from `resolve_derivations`
+        assert_eq!(map.find(0x4000_1008).count(), 0);
+
+        let device = device.resolve_derivations().unwrap();
+        let map = device.address_map();
+        let found: Vec<_> = map.find(0x4000_1008).map(|r| r.path_name()).collect();
+        assert_eq!(found, vec!["UART1.SR"]);
+        assert_eq!(map.registers().len(), 6);
+    }
+}
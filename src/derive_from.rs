@@ -1,5 +1,11 @@
+//! `derivedFrom` support.
+//! This module merges derived elements with their base and resolves the references
+//! found in a whole device
+
+use crate::error::*;
 use crate::{
-    ClusterInfo, EnumeratedValues, FieldInfo, Peripheral, RegisterInfo, RegisterProperties,
+    Cluster, ClusterInfo, Device, EnumeratedValues, Field, FieldInfo, PeripheralInfo, Register,
+    RegisterCluster, RegisterInfo, RegisterProperties,
 };
 
 /// Fill empty fields of structure with values of other structure
@@ -27,7 +33,7 @@ impl DeriveFrom for ClusterInfo {
 impl DeriveFrom for EnumeratedValues {
     fn derive_from(&self, other: &Self) -> Self {
         let mut derived = self.clone();
-        derived.usage = derived.usage.or_else(|| other.usage.clone());
+        derived.usage = derived.usage.or(other.usage);
         if derived.values.is_empty() {
             derived.values = other.values.clone();
         }
@@ -35,15 +41,32 @@ impl DeriveFrom for EnumeratedValues {
     }
 }
 
-impl DeriveFrom for Peripheral {
+impl DeriveFrom for PeripheralInfo {
     fn derive_from(&self, other: &Self) -> Self {
         let mut derived = self.clone();
+        derived.version = derived.version.or_else(|| other.version.clone());
+        derived.display_name = derived.display_name.or_else(|| other.display_name.clone());
         derived.group_name = derived.group_name.or_else(|| other.group_name.clone());
         derived.description = derived.description.or_else(|| other.description.clone());
+        derived.prepend_to_name = derived
+            .prepend_to_name
+            .or_else(|| other.prepend_to_name.clone());
+        derived.append_to_name = derived
+            .append_to_name
+            .or_else(|| other.append_to_name.clone());
+        derived.header_struct_name = derived
+            .header_struct_name
+            .or_else(|| other.header_struct_name.clone());
+        derived.disable_condition = derived
+            .disable_condition
+            .or_else(|| other.disable_condition.clone());
         derived.default_register_properties = derived
             .default_register_properties
             .derive_from(&other.default_register_properties);
         derived.registers = derived.registers.or_else(|| other.registers.clone());
+        if derived.address_block.is_empty() {
+            derived.address_block = other.address_block.clone();
+        }
         if derived.interrupt.is_empty() {
             derived.interrupt = other.interrupt.clone();
         }
@@ -59,22 +82,26 @@ impl DeriveFrom for RegisterInfo {
         derived.access = derived.access.or(other.access);
         derived.reset_value = derived.reset_value.or(other.reset_value);
         derived.reset_mask = derived.reset_mask.or(other.reset_mask);
+        derived.protection = derived.protection.or(other.protection);
+        derived.data_type = derived.data_type.or(other.data_type);
         derived.fields = derived.fields.or_else(|| other.fields.clone());
         derived.write_constraint = derived.write_constraint.or(other.write_constraint);
         derived.modified_write_values = derived
             .modified_write_values
             .or(other.modified_write_values);
+        derived.read_action = derived.read_action.or(other.read_action);
         derived
     }
 }
 
 impl DeriveFrom for RegisterProperties {
     fn derive_from(&self, other: &Self) -> Self {
-        let mut derived = self.clone();
+        let mut derived = *self;
         derived.size = derived.size.or(other.size);
         derived.reset_value = derived.reset_value.or(other.reset_value);
         derived.reset_mask = derived.reset_mask.or(other.reset_mask);
         derived.access = derived.access.or(other.access);
+        derived.protection = derived.protection.or(other.protection);
         derived
     }
 }
@@ -91,6 +118,579 @@ impl DeriveFrom for FieldInfo {
         derived.modified_write_values = derived
             .modified_write_values
             .or(other.modified_write_values);
+        derived.read_action = derived.read_action.or(other.read_action);
         derived
     }
 }
+
+/// Kind of SVD element that can carry a `derivedFrom` attribute
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Device,
+    Peripheral,
+    Cluster,
+    Register,
+    Field,
+    EnumeratedValues,
+}
+
+/// Borrowed view over the nodes of a device tree
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Device(&'a Device),
+    Peripheral(&'a PeripheralInfo),
+    Cluster(&'a Cluster),
+    Register(&'a Register),
+    Field(&'a Field),
+    EnumeratedValues(&'a EnumeratedValues),
+}
+
+impl<'a> Node<'a> {
+    fn kind(&self) -> Kind {
+        match self {
+            Node::Device(_) => Kind::Device,
+            Node::Peripheral(_) => Kind::Peripheral,
+            Node::Cluster(_) => Kind::Cluster,
+            Node::Register(_) => Kind::Register,
+            Node::Field(_) => Kind::Field,
+            Node::EnumeratedValues(_) => Kind::EnumeratedValues,
+        }
+    }
+
+    fn name(&self) -> Option<&'a str> {
+        match self {
+            Node::Device(d) => Some(&d.name),
+            Node::Peripheral(p) => Some(&p.name),
+            Node::Cluster(c) => Some(&c.name),
+            Node::Register(r) => Some(&r.name),
+            Node::Field(f) => Some(&f.name),
+            Node::EnumeratedValues(e) => e.name.as_deref(),
+        }
+    }
+
+    fn derived_from(&self) -> Option<&'a str> {
+        match self {
+            Node::Device(_) => None,
+            Node::Peripheral(p) => p.derived_from.as_deref(),
+            Node::Cluster(c) => c.derived_from.as_deref(),
+            Node::Register(r) => r.derived_from.as_deref(),
+            Node::Field(f) => f.derived_from.as_deref(),
+            Node::EnumeratedValues(e) => e.derived_from.as_deref(),
+        }
+    }
+
+    fn children(&self) -> Vec<Node<'a>> {
+        fn register_cluster(rc: &RegisterCluster) -> Node<'_> {
+            match rc {
+                RegisterCluster::Register(r) => Node::Register(r),
+                RegisterCluster::Cluster(c) => Node::Cluster(c),
+            }
+        }
+        match self {
+            Node::Device(d) => d.peripherals.iter().map(|p| Node::Peripheral(p)).collect(),
+            Node::Peripheral(p) => p.registers.iter().flatten().map(register_cluster).collect(),
+            Node::Cluster(c) => c.children.iter().map(register_cluster).collect(),
+            Node::Register(r) => r.fields.iter().flatten().map(Node::Field).collect(),
+            Node::Field(f) => f
+                .enumerated_values
+                .iter()
+                .map(Node::EnumeratedValues)
+                .collect(),
+            Node::EnumeratedValues(_) => Vec::new(),
+        }
+    }
+}
+
+/// Element with its `derivedFrom` reference resolved, ready to be put back in the tree
+enum Derived {
+    Peripheral(PeripheralInfo),
+    Cluster(ClusterInfo),
+    Register(RegisterInfo),
+    Field(FieldInfo),
+    EnumeratedValues(EnumeratedValues),
+}
+
+impl Derived {
+    fn new(node: Node, base: Node) -> Self {
+        match (node, base) {
+            (Node::Peripheral(p), Node::Peripheral(b)) => {
+                let mut p = p.derive_from(b);
+                p.derived_from = None;
+                Derived::Peripheral(p)
+            }
+            (Node::Cluster(c), Node::Cluster(b)) => {
+                let mut c = c.derive_from(b);
+                c.derived_from = None;
+                Derived::Cluster(c)
+            }
+            (Node::Register(r), Node::Register(b)) => {
+                let mut r = r.derive_from(b);
+                r.derived_from = None;
+                Derived::Register(r)
+            }
+            (Node::Field(f), Node::Field(b)) => {
+                let mut f = f.derive_from(b);
+                f.derived_from = None;
+                Derived::Field(f)
+            }
+            (Node::EnumeratedValues(e), Node::EnumeratedValues(b)) => {
+                let mut e = e.derive_from(b);
+                e.derived_from = None;
+                Derived::EnumeratedValues(e)
+            }
+            _ => unreachable!("lookup only returns nodes of the same kind"),
+        }
+    }
+}
+
+/// Returns the nodes from the root down to the node at `path` (inclusive)
+fn nodes_at<'a>(device: &'a Device, path: &[usize]) -> Vec<Node<'a>> {
+    let mut nodes = vec![Node::Device(device)];
+    for &i in path {
+        let next = nodes[nodes.len() - 1].children()[i];
+        nodes.push(next);
+    }
+    nodes
+}
+
+/// Dotted name of the node at `path`, e.g. `PERIPH.CLUSTER.REG.FIELD`
+fn path_name(device: &Device, path: &[usize]) -> String {
+    nodes_at(device, path)[1..]
+        .iter()
+        .map(|n| n.name().unwrap_or("_"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Collects index paths of all nodes of `kind` which still have a `derivedFrom`
+fn collect_derived(node: Node, kind: Kind, path: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+    if node.kind() == kind {
+        if node.derived_from().is_some() {
+            out.push(path.clone());
+        }
+        return;
+    }
+    for (i, child) in node.children().into_iter().enumerate() {
+        path.push(i);
+        collect_derived(child, kind, path, out);
+        path.pop();
+    }
+}
+
+/// Follows the dotted `segments` down from `scope`
+fn descend<'a>(scope: Node<'a>, segments: &[&str], kind: Kind) -> Option<(Vec<usize>, Node<'a>)> {
+    let (first, rest) = segments.split_first()?;
+    for (i, child) in scope.children().into_iter().enumerate() {
+        if child.name() != Some(*first) {
+            continue;
+        }
+        if rest.is_empty() {
+            if child.kind() == kind {
+                return Some((vec![i], child));
+            }
+        } else if let Some((mut path, node)) = descend(child, rest, kind) {
+            path.insert(0, i);
+            return Some((path, node));
+        }
+    }
+    None
+}
+
+/// Searches every descendant of `scope` for a node of `kind` called `name`
+fn search<'a>(scope: Node<'a>, name: &str, kind: Kind) -> Option<(Vec<usize>, Node<'a>)> {
+    for (i, child) in scope.children().into_iter().enumerate() {
+        let found = if child.kind() == kind && child.name() == Some(name) {
+            Some((Vec::new(), child))
+        } else {
+            search(child, name, kind)
+        };
+        if let Some((mut path, node)) = found {
+            path.insert(0, i);
+            return Some((path, node));
+        }
+    }
+    None
+}
+
+/// Looks up the element referenced by the node at `path`.
+///
+/// The reference is resolved relative to each enclosing scope, from the
+/// innermost one up to the device, so both sibling names and full dotted
+/// paths (`PERIPH.CLUSTER.REG.FIELD`) are accepted.
+/// `enumeratedValues` may also be referenced by their name alone
+/// from anywhere inside the enclosing scopes.
+fn lookup<'a>(device: &'a Device, path: &[usize]) -> Option<(Vec<usize>, Node<'a>)> {
+    let nodes = nodes_at(device, path);
+    let node = nodes[nodes.len() - 1];
+    let kind = node.kind();
+    let reference = node.derived_from()?;
+    let segments: Vec<&str> = reference.split('.').collect();
+    for depth in (0..nodes.len() - 1).rev() {
+        let scope = nodes[depth];
+        let found = descend(scope, &segments, kind).or_else(|| {
+            if kind == Kind::EnumeratedValues && segments.len() == 1 {
+                search(scope, reference, kind)
+            } else {
+                None
+            }
+        });
+        if let Some((mut target, node)) = found {
+            let mut full = path[..depth].to_vec();
+            full.append(&mut target);
+            return Some((full, node));
+        }
+    }
+    None
+}
+
+fn apply_field(field: &mut Field, path: &[usize], value: Derived) {
+    match (path, value) {
+        ([], Derived::Field(info)) => **field = info,
+        ([i], Derived::EnumeratedValues(ev)) => field.enumerated_values[*i] = ev,
+        _ => unreachable!(),
+    }
+}
+
+fn apply_register_cluster(rc: &mut RegisterCluster, path: &[usize], value: Derived) {
+    match rc {
+        RegisterCluster::Cluster(cluster) => match path.split_first() {
+            None => {
+                if let Derived::Cluster(info) = value {
+                    **cluster = info;
+                }
+            }
+            Some((i, rest)) => apply_register_cluster(&mut cluster.children[*i], rest, value),
+        },
+        RegisterCluster::Register(register) => match path.split_first() {
+            None => {
+                if let Derived::Register(info) = value {
+                    **register = info;
+                }
+            }
+            Some((i, rest)) => {
+                let fields = register.fields.as_mut().expect("path points to a field");
+                apply_field(&mut fields[*i], rest, value)
+            }
+        },
+    }
+}
+
+fn apply(device: &mut Device, path: &[usize], value: Derived) {
+    let (i, rest) = path.split_first().expect("path points to an element");
+    let peripheral = &mut device.peripherals[*i];
+    match rest.split_first() {
+        None => {
+            if let Derived::Peripheral(p) = value {
+                **peripheral = p;
+            }
+        }
+        Some((i, rest)) => {
+            let registers = peripheral
+                .registers
+                .as_mut()
+                .expect("path points to a register or cluster");
+            apply_register_cluster(&mut registers[*i], rest, value)
+        }
+    }
+}
+
+/// Resolves every `derivedFrom` reference in `device`.
+///
+/// Peripherals are derived first, then clusters, registers, fields and
+/// enumerated values, so that elements copied from a base are themselves
+/// resolved in their new location. Chains of derivations are applied in
+/// dependency order.
+pub fn resolve_derivations(device: &Device) -> Result<Device> {
+    let (device, problems) = resolve_all(device);
+    // A dangling reference also leaves the elements derived from it unresolved,
+    // so it is reported before cycles
+    let first = problems
+        .iter()
+        .find(|p| matches!(p.error, DeriveError::NotFound(..)))
+        .or_else(|| problems.first());
+    match first {
+        Some(problem) => Err(problem.error.clone().into()),
+        None => Ok(device),
+    }
+}
+
+/// Unresolvable `derivedFrom` reference
+pub(crate) struct Problem {
+    /// Elements from the peripheral down to the element with the reference
+    pub(crate) path: Vec<PathElement>,
+    pub(crate) error: DeriveError,
+}
+
+/// Resolves as many `derivedFrom` references as possible and returns the device
+/// with the problems of the others: every dangling reference and every element
+/// on a cycle. Elements derived from them are left unresolved without a report.
+/// Unresolved elements keep their `derivedFrom` reference
+pub(crate) fn resolve_all(device: &Device) -> (Device, Vec<Problem>) {
+    let mut device = device.clone();
+    let mut problems = Vec::new();
+    for &kind in &[
+        Kind::Peripheral,
+        Kind::Cluster,
+        Kind::Register,
+        Kind::Field,
+        Kind::EnumeratedValues,
+    ] {
+        loop {
+            let mut pending = Vec::new();
+            collect_derived(Node::Device(&device), kind, &mut Vec::new(), &mut pending);
+            if pending.is_empty() {
+                break;
+            }
+
+            let mut updates = Vec::new();
+            for path in &pending {
+                if let Some((target, base)) = lookup(&device, path) {
+                    // Wait until the base itself is resolved
+                    if base.derived_from().is_none() && &target != path {
+                        let nodes = nodes_at(&device, path);
+                        updates.push((path.clone(), Derived::new(nodes[nodes.len() - 1], base)));
+                    }
+                }
+            }
+
+            if updates.is_empty() {
+                // Nothing can progress: report the dangling references and cycles
+                for path in &pending {
+                    let error = match lookup(&device, path) {
+                        None => DeriveError::NotFound,
+                        Some(_) if on_cycle(&device, path) => DeriveError::Cycle,
+                        Some(_) => continue,
+                    };
+                    let nodes = nodes_at(&device, path);
+                    let reference = nodes[nodes.len() - 1].derived_from().unwrap_or_default();
+                    problems.push(Problem {
+                        path: path_elements(&device, path),
+                        error: error(path_name(&device, path), reference.to_string()),
+                    });
+                }
+                break;
+            }
+
+            for (path, value) in updates {
+                apply(&mut device, &path, value);
+            }
+        }
+    }
+    (device, problems)
+}
+
+/// Returns `true` if following the references from the node at `path` leads back to it
+fn on_cycle(device: &Device, path: &[usize]) -> bool {
+    let mut current = path.to_vec();
+    let mut visited = Vec::new();
+    while let Some((target, _)) = lookup(device, &current) {
+        if target == path {
+            return true;
+        }
+        if visited.contains(&target) {
+            return false;
+        }
+        visited.push(target.clone());
+        current = target;
+    }
+    false
+}
+
+/// Tags and names of the nodes at `path`, from the peripheral down
+fn path_elements(device: &Device, path: &[usize]) -> Vec<PathElement> {
+    nodes_at(device, path)[1..]
+        .iter()
+        .map(|n| PathElement {
+            tag: match n.kind() {
+                Kind::Device => "device",
+                Kind::Peripheral => "peripheral",
+                Kind::Cluster => "cluster",
+                Kind::Register => "register",
+                Kind::Field => "field",
+                Kind::EnumeratedValues => "enumeratedValues",
+            },
+            name: n.name().unwrap_or_default().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(peripherals: &str) -> Device {
+        crate::parse(&format!(
+            "<device><name>TEST</name><peripherals>{}</peripherals></device>",
+            peripherals
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve() {
+        let d = device(
+            "
+            <peripheral derivedFrom=\"TIMER0\">
+                <name>TIMER1</name>
+                <baseAddress>0x40001000</baseAddress>
+            </peripheral>
+            <peripheral>
+                <name>TIMER0</name>
+                <version>1.0</version>
+                <description>Timer</description>
+                <baseAddress>0x40000000</baseAddress>
+                <addressBlock>
+                    <offset>0x0</offset>
+                    <size>0x100</size>
+                    <usage>registers</usage>
+                </addressBlock>
+                <registers>
+                    <register>
+                        <name>CR</name>
+                        <addressOffset>0x0</addressOffset>
+                        <size>32</size>
+                        <fields>
+                            <field>
+                                <name>EN</name>
+                                <bitOffset>0</bitOffset>
+                                <bitWidth>1</bitWidth>
+                                <enumeratedValues>
+                                    <name>ENABLE</name>
+                                    <enumeratedValue><name>OFF</name><value>0</value></enumeratedValue>
+                                    <enumeratedValue><name>ON</name><value>1</value></enumeratedValue>
+                                </enumeratedValues>
+                            </field>
+                            <field derivedFrom=\"EN\">
+                                <name>EN2</name>
+                                <bitOffset>1</bitOffset>
+                                <bitWidth>1</bitWidth>
+                            </field>
+                            <field>
+                                <name>EN3</name>
+                                <bitOffset>2</bitOffset>
+                                <bitWidth>1</bitWidth>
+                                <enumeratedValues derivedFrom=\"ENABLE\"></enumeratedValues>
+                            </field>
+                        </fields>
+                    </register>
+                    <register derivedFrom=\"CR2\">
+                        <name>CR3</name>
+                        <addressOffset>0x8</addressOffset>
+                    </register>
+                    <register derivedFrom=\"TIMER0.CR\">
+                        <name>CR2</name>
+                        <addressOffset>0x4</addressOffset>
+                    </register>
+                </registers>
+            </peripheral>
+            ",
+        );
+        let d = d.resolve_derivations().unwrap();
+
+        let timer1 = &d.peripherals[0];
+        assert_eq!(timer1.derived_from, None);
+        assert_eq!(timer1.base_address, 0x40001000);
+        assert_eq!(timer1.description.as_deref(), Some("Timer"));
+        assert_eq!(timer1.version.as_deref(), Some("1.0"));
+        assert_eq!(timer1.address_block, d.peripherals[1].address_block);
+        assert_eq!(timer1.address_block[0].size, 0x100);
+
+        for p in &d.peripherals {
+            let regs: Vec<_> = p.reg_iter().collect();
+            assert_eq!(regs.len(), 3);
+            for r in &regs {
+                assert_eq!(r.derived_from, None);
+                assert_eq!(r.size, Some(32));
+                let fields = r.fields.as_ref().unwrap();
+                assert_eq!(fields.len(), 3);
+                for f in fields {
+                    assert_eq!(f.derived_from, None);
+                    assert_eq!(f.enumerated_values[0].derived_from, None);
+                    assert_eq!(f.enumerated_values[0].values.len(), 2);
+                }
+            }
+            assert_eq!(regs[1].name, "CR3");
+            assert_eq!(regs[1].address_offset, 8);
+        }
+    }
+
+    #[test]
+    fn dangling() {
+        let d = device(
+            "
+            <peripheral derivedFrom=\"TIMER2\">
+                <name>TIMER1</name>
+                <baseAddress>0x40001000</baseAddress>
+            </peripheral>
+            ",
+        );
+        let err = d.resolve_derivations().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::NotFound(
+                "TIMER1".to_string(),
+                "TIMER2".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn dangling_base() {
+        let d = device(
+            "
+            <peripheral derivedFrom=\"TIMER2\">
+                <name>TIMER1</name>
+                <baseAddress>0x40001000</baseAddress>
+            </peripheral>
+            <peripheral derivedFrom=\"TIMER3\">
+                <name>TIMER2</name>
+                <baseAddress>0x40002000</baseAddress>
+            </peripheral>
+            ",
+        );
+        let err = d.resolve_derivations().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::NotFound(
+                "TIMER2".to_string(),
+                "TIMER3".to_string()
+            ))
+        );
+
+        let (d, problems) = resolve_all(&d);
+        assert_eq!(problems.len(), 1);
+        let references: Vec<_> = d
+            .peripherals
+            .iter()
+            .map(|p| p.derived_from.as_deref())
+            .collect();
+        assert_eq!(references, vec![Some("TIMER2"), Some("TIMER3")]);
+    }
+
+    #[test]
+    fn cycle() {
+        let d = device(
+            "
+            <peripheral>
+                <name>TIMER0</name>
+                <baseAddress>0x40000000</baseAddress>
+                <registers>
+                    <register derivedFrom=\"B\">
+                        <name>A</name>
+                        <addressOffset>0x0</addressOffset>
+                    </register>
+                    <register derivedFrom=\"A\">
+                        <name>B</name>
+                        <addressOffset>0x4</addressOffset>
+                    </register>
+                </registers>
+            </peripheral>
+            ",
+        );
+        let err = d.resolve_derivations().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::Cycle("TIMER0.A".to_string(), "B".to_string()))
+        );
+    }
+}
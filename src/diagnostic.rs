@@ -0,0 +1,403 @@
+//! Diagnostics.
+//! This module parses SVD files collecting all problems instead of stopping at the first one
+
+use xmltree::{Element, XMLNode};
+
+use crate::error::{element_path, ElementPath, Error};
+use crate::parse::Config;
+use crate::position::{self, SourceLocation, SourceMap};
+use crate::svd::vendorextensions::parse_extensions;
+use crate::svd::{Cluster, Device, Field, Peripheral, Register, RegisterCluster};
+use crate::types::Parse;
+
+/// Severity of a diagnostic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The element could not be parsed and is missing from the device
+    Error,
+    /// The element was parsed, but the device is not consistent
+    Warning,
+}
+
+/// Problem found while parsing an SVD file
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Names of the peripheral, clusters, register and field containing the problem
+    pub path: Vec<String>,
+
+    /// Location of the offending element, if known
+    pub location: Option<SourceLocation>,
+
+    pub error: Error,
+}
+
+impl Diagnostic {
+    /// Returns the dotted path of the element, e.g. `PERIPH.REG.FIELD`
+    pub fn path_name(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Parses the contents of an SVD (XML) string without stopping at the first error.
+///
+/// Peripherals, clusters, registers and fields which fail to parse are left out
+/// of the device and reported as errors. Vendor extensions which fail the parsers
+/// registered in `config` are reported as errors and have no typed value.
+/// Every dangling `derivedFrom` reference and every element on a cycle of
+/// references is reported as a warning.
+/// The device is `None` if the XML is malformed or the device itself is invalid
+pub fn parse_with_diagnostics(xml: &str, config: &Config) -> (Option<Device>, Vec<Diagnostic>) {
+    let xml = crate::trim_utf8_bom(xml);
+    let tree = match Element::parse(xml.as_bytes()) {
+        Ok(tree) => tree,
+        Err(e) => {
+            let diagnostic = Diagnostic {
+                severity: Severity::Error,
+                path: Vec::new(),
+                location: None,
+                error: e.into(),
+            };
+            return (None, vec![diagnostic]);
+        }
+    };
+
+    let mut pruner = Pruner {
+        map: SourceMap::new(xml),
+        root: &tree,
+        path: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let peripherals = tree
+        .get_child("peripherals")
+        .map(|ps| pruner.peripherals(ps))
+        .unwrap_or_default();
+    let Pruner {
+        map,
+        mut diagnostics,
+        ..
+    } = pruner;
+
+    let mut device = match Device::parse_with(&tree, peripherals) {
+        Ok(device) => device,
+        Err(error) => {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                path: Vec::new(),
+                location: map.location(&tree, &tree),
+                error: error.into(),
+            });
+            return (None, diagnostics);
+        }
+    };
+    for error in parse_extensions(&mut device, config) {
+        let path = element_path(&error);
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            path: path.iter().skip(1).map(|p| p.name.clone()).collect(),
+            location: position::find_path(&tree, &path).and_then(|e| map.location(&tree, e)),
+            error: error.into(),
+        });
+    }
+    for problem in crate::derive_from::resolve_all(&device).1 {
+        let elem = position::find_path(&tree, &problem.path);
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            path: problem.path.iter().map(|p| p.name.clone()).collect(),
+            location: elem.and_then(|e| map.location(&tree, e)),
+            error: anyhow::Error::from(problem.error)
+                .context(ElementPath(problem.path))
+                .into(),
+        });
+    }
+    (Some(device), diagnostics)
+}
+
+/// Parses the elements of a tree, leaving out the ones which fail to parse.
+///
+/// Every element is parsed once: containers are built from their already
+/// parsed children
+struct Pruner<'a> {
+    map: SourceMap<'a>,
+    root: &'a Element,
+    path: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Pruner<'a> {
+    fn peripherals(&mut self, elem: &'a Element) -> Vec<Peripheral> {
+        elements(elem)
+            .filter_map(|p| {
+                self.parse(p, |pruner| {
+                    let registers = p.get_child("registers").map(|rs| pruner.registers(rs));
+                    Peripheral::parse_with(p, registers)
+                })
+            })
+            .collect()
+    }
+
+    fn registers(&mut self, elem: &'a Element) -> Vec<RegisterCluster> {
+        elements(elem)
+            .filter_map(|e| self.register_cluster(e))
+            .collect()
+    }
+
+    fn register_cluster(&mut self, elem: &'a Element) -> Option<RegisterCluster> {
+        self.parse(elem, |pruner| match elem.name.as_str() {
+            "register" => {
+                let fields = elem.get_child("fields").map(|fs| pruner.fields(fs));
+                Register::parse_with(elem, fields).map(RegisterCluster::Register)
+            }
+            "cluster" => {
+                let children = elements(elem)
+                    .filter(|e| e.name == "register" || e.name == "cluster")
+                    .filter_map(|e| pruner.register_cluster(e))
+                    .collect();
+                Cluster::parse_with(elem, children).map(RegisterCluster::Cluster)
+            }
+            _ => RegisterCluster::parse(elem),
+        })
+    }
+
+    fn fields(&mut self, elem: &'a Element) -> Vec<Field> {
+        elements(elem)
+            .filter_map(|f| self.parse(f, |_| Field::parse(f)))
+            .collect()
+    }
+
+    /// Parses `elem` with `parse`, or reports the error and returns `None`
+    fn parse<T>(
+        &mut self,
+        elem: &'a Element,
+        parse: impl FnOnce(&mut Self) -> anyhow::Result<T>,
+    ) -> Option<T> {
+        let name = elem
+            .get_child("name")
+            .and_then(|n| n.get_text())
+            .unwrap_or_default()
+            .into_owned();
+        self.path.push(name);
+        let parsed = match parse(self) {
+            Ok(parsed) => Some(parsed),
+            Err(error) => {
+                let offending = position::error_element(elem, &error).unwrap_or(elem);
+                self.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    path: self.path.clone(),
+                    location: self.map.location(self.root, offending),
+                    error: error.into(),
+                });
+                None
+            }
+        };
+        self.path.pop();
+        parsed
+    }
+}
+
+fn elements(elem: &Element) -> impl Iterator<Item = &Element> {
+    elem.children.iter().filter_map(XMLNode::as_element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{DeriveError, ErrorKind};
+
+    #[test]
+    fn collect_all() {
+        let (device, diagnostics) = parse_with_diagnostics(
+            "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIMER0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR</name>
+          <addressOffset>0x0</addressOffset>
+          <fields>
+            <field>
+              <name>EN</name>
+              <bitOffset>0</bitOffset>
+              <bitWidth>1</bitWidth>
+            </field>
+            <field>
+              <name>MODE</name>
+              <bitOffset>1</bitOffset>
+              <bitWidth>2</bitWidth>
+              <access>read-only-ish</access>
+            </field>
+          </fields>
+        </register>
+        <register>
+          <name>SR</name>
+        </register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom=\"TIMER9\">
+      <name>TIMER1</name>
+      <baseAddress>0x40001000</baseAddress>
+    </peripheral>
+    <peripheral derivedFrom=\"TIMER3\">
+      <name>TIMER2</name>
+      <baseAddress>0x40002000</baseAddress>
+    </peripheral>
+    <peripheral derivedFrom=\"TIMER2\">
+      <name>TIMER3</name>
+      <baseAddress>0x40003000</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>",
+            &Config::default(),
+        );
+
+        let device = device.unwrap();
+        assert_eq!(device.peripherals.len(), 4);
+        let registers: Vec<_> = device.peripherals[0].reg_iter().collect();
+        assert_eq!(registers.len(), 1);
+        let fields: Vec<_> = registers[0].fields.iter().flatten().collect();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "EN");
+
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.severity,
+                    d.path_name(),
+                    d.location.as_ref().map(|l| l.span.line),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Error, "TIMER0.CR.MODE".to_string(), Some(21)),
+                (Severity::Error, "TIMER0.SR".to_string(), Some(25)),
+                (Severity::Warning, "TIMER1".to_string(), Some(30)),
+                (Severity::Warning, "TIMER2".to_string(), Some(34)),
+                (Severity::Warning, "TIMER3".to_string(), Some(38)),
+            ]
+        );
+        assert!(matches!(
+            diagnostics[2].error.kind(),
+            ErrorKind::Derive(DeriveError::NotFound(..))
+        ));
+        assert!(matches!(
+            diagnostics[3].error.kind(),
+            ErrorKind::Derive(DeriveError::Cycle(..))
+        ));
+        for d in &diagnostics[2..] {
+            let path: Vec<_> = d.error.path().into_iter().map(|p| p.name).collect();
+            assert_eq!(path, d.path);
+        }
+    }
+
+    #[test]
+    fn pruned_child() {
+        let (_, diagnostics) = parse_with_diagnostics(
+            "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIMER0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR</name>
+          <addressOffset>0x0</addressOffset>
+          <fields>
+            <field>
+              <name>EN</name>
+              <bitRange>[x:0]</bitRange>
+            </field>
+          </fields>
+          <access>read-only-ish</access>
+        </register>
+        <register>
+          <name>SR</name>
+          <addressOffset>0x4</addressOffset>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>",
+            &Config::default(),
+        );
+
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.path_name(), d.location.as_ref().map(|l| l.span.line)))
+            .collect();
+        // the register is parsed without its invalid field
+        assert_eq!(
+            summary,
+            vec![
+                ("TIMER0.CR.EN".to_string(), Some(12)),
+                ("TIMER0.CR".to_string(), Some(17)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pruned_cluster() {
+        let (device, diagnostics) = parse_with_diagnostics(
+            "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIMER0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <cluster>
+          <name>CH</name>
+          <addressOffset>0x0</addressOffset>
+          <register>
+            <name>CR</name>
+            <addressOffset>0x0</addressOffset>
+          </register>
+          <register>
+            <name>SR</name>
+          </register>
+        </cluster>
+        <interrupt>
+          <name>IRQ</name>
+        </interrupt>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>",
+            &Config::default(),
+        );
+
+        let device = device.unwrap();
+        let registers: Vec<_> = device.peripherals[0]
+            .reg_iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(registers, vec!["CR"]);
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.path_name(), d.location.as_ref().map(|l| l.span.line)))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("TIMER0.CH.SR".to_string(), Some(15)),
+                ("TIMER0.IRQ".to_string(), Some(19)),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let (device, diagnostics) =
+            parse_with_diagnostics("<device><name>TEST</device>", &Config::default());
+        assert!(device.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+}
@@ -1,7 +1,7 @@
 //! SVD Element Extensions.
 //! This module is extends xmltree::Element objects with convenience methods
 
-use xmltree::Element;
+use xmltree::{Element, ElementPredicate, XMLNode};
 
 use crate::types::{BoolParse, Parse};
 
@@ -11,13 +11,12 @@ use crate::error::*;
 pub trait ElementExt {
     fn get_child_text_opt<K>(&self, k: K) -> Result<Option<String>>
     where
-        String: PartialEq<K>;
+        K: ElementPredicate;
     fn get_child_text<K>(&self, k: K) -> Result<String>
     where
-        String: PartialEq<K>,
-        K: core::fmt::Display + Clone;
+        K: ElementPredicate + core::fmt::Display + Clone;
 
-    fn get_text(&self) -> Result<String>;
+    fn get_content(&self) -> Result<String>;
 
     fn get_child_elem<'a>(&'a self, n: &str) -> Result<&'a Element>;
     fn get_child_u32(&self, n: &str) -> Result<u32>;
@@ -33,10 +32,10 @@ pub trait ElementExt {
 impl ElementExt for Element {
     fn get_child_text_opt<K>(&self, k: K) -> Result<Option<String>>
     where
-        String: PartialEq<K>,
+        K: ElementPredicate,
     {
         if let Some(child) = self.get_child(k) {
-            match child.get_text() {
+            match child.get_content() {
                 Err(e) => match e.downcast_ref() {
                     // if tag is empty just ignore it
                     Some(SVDError::EmptyTag(_, _)) => Ok(None),
@@ -50,21 +49,20 @@ impl ElementExt for Element {
     }
     fn get_child_text<K>(&self, k: K) -> Result<String>
     where
-        String: PartialEq<K>,
-        K: core::fmt::Display + Clone,
+        K: ElementPredicate + core::fmt::Display + Clone,
     {
         self.get_child_text_opt(k.clone())?
-            .ok_or_else(|| SVDError::MissingTag(self.clone(), format!("{}", k)).into())
+            .ok_or_else(|| SVDError::MissingTag(self.into(), format!("{}", k)).into())
     }
 
     /// Get text contained by an XML Element
-    fn get_text(&self) -> Result<String> {
-        match self.text.as_ref() {
-            Some(s) => Ok(s.clone()),
+    fn get_content(&self) -> Result<String> {
+        match self.get_text() {
+            Some(s) => Ok(s.into_owned()),
             // FIXME: Doesn't look good because SVDError doesn't format by itself. We already
             // capture the element and this information can be used for getting the name
             // This would fix ParseError
-            None => Err(SVDError::EmptyTag(self.clone(), self.name.clone()).into()),
+            None => Err(SVDError::EmptyTag(self.into(), self.name.clone()).into()),
         }
     }
 
@@ -72,20 +70,20 @@ impl ElementExt for Element {
     fn get_child_elem<'a>(&'a self, n: &str) -> Result<&'a Element> {
         match self.get_child(n) {
             Some(s) => Ok(s),
-            None => Err(SVDError::MissingTag(self.clone(), n.to_string()).into()),
+            None => Err(SVDError::MissingTag(self.into(), n.to_string()).into()),
         }
     }
 
     /// Get a u32 value from a named child element
     fn get_child_u32(&self, n: &str) -> Result<u32> {
         let s = self.get_child_elem(n)?;
-        u32::parse(&s).context(SVDError::ParseError(self.clone()))
+        u32::parse(s).with_context(|| SVDError::ParseError(s.into()))
     }
 
     /// Get a u64 value from a named child element
     fn get_child_u64(&self, n: &str) -> Result<u64> {
         let s = self.get_child_elem(n)?;
-        u64::parse(&s).context(SVDError::ParseError(self.clone()))
+        u64::parse(s).with_context(|| SVDError::ParseError(s.into()))
     }
 
     /// Get a bool value from a named child element
@@ -103,8 +101,8 @@ impl ElementExt for Element {
 
     fn debug(&self) {
         println!("<{}>", self.name);
-        for c in &self.children {
-            println!("{}: {:?}", c.name, c.text)
+        for c in self.children.iter().filter_map(XMLNode::as_element) {
+            println!("{}: {:?}", c.name, c.get_text())
         }
         println!("</{}>", self.name);
     }
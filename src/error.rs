@@ -2,14 +2,91 @@
 //! This module defines error types and messages for SVD parsing and encoding
 
 pub use anyhow::{Context, Result};
-use core::u64;
 #[cfg(feature = "strict")]
 use once_cell::sync::Lazy;
 #[cfg(feature = "strict")]
 use regex::Regex;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
-#[allow(clippy::large_enum_variant, clippy::upper_case_acronyms)]
+use crate::position::Span;
+
+/// Compact reference to the XML element an error refers to.
+///
+/// Only the tag of the element is copied when the error is created,
+/// the path and location are filled in by [`parse`](crate::parse),
+/// which finds the element by its tag and content inside the elements of the path
+#[derive(Clone, Eq)]
+pub struct ElementRef {
+    /// XML tag of the element
+    pub tag: String,
+    /// Elements named in the error contexts, outermost first
+    pub path: Vec<PathElement>,
+    /// Location of the element in the SVD source
+    pub span: Option<Span>,
+    // Hash of the attributes and text of the element and of its children with text,
+    // which stays the same in copies of the tree with some elements left out
+    fingerprint: u64,
+}
+
+impl ElementRef {
+    pub(crate) fn new(elem: &Element) -> Self {
+        Self {
+            tag: elem.name.clone(),
+            path: Vec::new(),
+            span: None,
+            fingerprint: fingerprint(elem),
+        }
+    }
+
+    /// Returns `true` if the error may have been created for `elem`,
+    /// that is `elem` or a copy of it
+    pub(crate) fn is(&self, elem: &Element) -> bool {
+        self.tag == elem.name && self.fingerprint == fingerprint(elem)
+    }
+}
+
+/// Hashes the attributes and text of `elem` and the tags and text of its children with text
+fn fingerprint(elem: &Element) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    let mut attributes: Vec<_> = elem.attributes.iter().collect();
+    attributes.sort();
+    attributes.hash(&mut hasher);
+    elem.get_text().hash(&mut hasher);
+    for child in elem.children.iter().filter_map(XMLNode::as_element) {
+        if let Some(text) = child.get_text() {
+            child.name.hash(&mut hasher);
+            text.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+impl From<&Element> for ElementRef {
+    fn from(elem: &Element) -> Self {
+        Self::new(elem)
+    }
+}
+
+impl core::fmt::Debug for ElementRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("ElementRef")
+            .field("tag", &self.tag)
+            .field("path", &self.path)
+            .field("span", &self.span)
+            .finish()
+    }
+}
+
+impl PartialEq for ElementRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag && self.path == other.path && self.span == other.span
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
 pub enum SVDError {
     #[error("Unknown endianness `{0}`")]
@@ -17,31 +94,39 @@ pub enum SVDError {
     // TODO: Needs context
     // TODO: Better name
     #[error("Expected a <{1}> tag, found none")]
-    MissingTag(Element, String),
+    MissingTag(ElementRef, String),
     #[error("Expected content in <{1}> tag, found none")]
-    EmptyTag(Element, String),
+    EmptyTag(ElementRef, String),
     #[error("ParseError")]
-    ParseError(Element),
+    ParseError(ElementRef),
     #[error("NameMismatch")]
-    NameMismatch(Element),
+    NameMismatch(ElementRef),
     #[error("unknown access variant '{1}' found")]
-    UnknownAccessType(Element, String),
+    UnknownAccessType(ElementRef, String),
     #[error("Bit range invalid, {1:?}")]
-    InvalidBitRange(Element, InvalidBitRange),
+    InvalidBitRange(ElementRef, InvalidBitRange),
     #[error("Unknown write constraint")]
-    UnknownWriteConstraint(Element),
+    UnknownWriteConstraint(ElementRef),
     #[error("Multiple wc found")]
-    MoreThanOneWriteConstraint(Element),
+    MoreThanOneWriteConstraint(ElementRef),
     #[error("Unknown usage variant")]
-    UnknownUsageVariant(Element),
+    UnknownUsageVariant(ElementRef),
     #[error("Expected a <{1}>, found ...")]
-    NotExpectedTag(Element, String),
+    NotExpectedTag(ElementRef, String),
     #[error("Invalid RegisterCluster (expected register or cluster), found {1}")]
-    InvalidRegisterCluster(Element, String),
+    InvalidRegisterCluster(ElementRef, String),
+    #[error("Invalid protection variant, found {1}")]
+    InvalidProtection(ElementRef, String),
     #[error("Invalid modifiedWriteValues variant, found {1}")]
-    InvalidModifiedWriteValues(Element, String),
+    InvalidModifiedWriteValues(ElementRef, String),
+    #[error("Invalid readAction variant, found {1}")]
+    InvalidReadAction(ElementRef, String),
+    #[error("Invalid dataType variant, found {1}")]
+    InvalidDataType(ElementRef, String),
     #[error("The content of the element could not be parsed to a boolean value {1}: {2}")]
-    InvalidBooleanValue(Element, String, core::str::ParseBoolError),
+    InvalidBooleanValue(ElementRef, String, core::str::ParseBoolError),
+    #[error("Expected {1} elements in dimIndex, found {2}")]
+    DimIndexLength(ElementRef, u32, usize),
     #[error("encoding method not implemented for svd object {0}")]
     EncodeNotImplemented(String),
     #[error("Error parsing SVD XML")]
@@ -56,6 +141,55 @@ pub enum SVDError {
     EmptyFields,
 }
 
+impl SVDError {
+    /// Returns the XML element the error refers to
+    pub fn element(&self) -> Option<&ElementRef> {
+        match self {
+            SVDError::MissingTag(e, _)
+            | SVDError::EmptyTag(e, _)
+            | SVDError::ParseError(e)
+            | SVDError::NameMismatch(e)
+            | SVDError::UnknownAccessType(e, _)
+            | SVDError::InvalidBitRange(e, _)
+            | SVDError::UnknownWriteConstraint(e)
+            | SVDError::MoreThanOneWriteConstraint(e)
+            | SVDError::UnknownUsageVariant(e)
+            | SVDError::NotExpectedTag(e, _)
+            | SVDError::InvalidRegisterCluster(e, _)
+            | SVDError::InvalidProtection(e, _)
+            | SVDError::InvalidModifiedWriteValues(e, _)
+            | SVDError::InvalidReadAction(e, _)
+            | SVDError::InvalidDataType(e, _)
+            | SVDError::InvalidBooleanValue(e, _, _)
+            | SVDError::DimIndexLength(e, _, _) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn element_mut(&mut self) -> Option<&mut ElementRef> {
+        match self {
+            SVDError::MissingTag(e, _)
+            | SVDError::EmptyTag(e, _)
+            | SVDError::ParseError(e)
+            | SVDError::NameMismatch(e)
+            | SVDError::UnknownAccessType(e, _)
+            | SVDError::InvalidBitRange(e, _)
+            | SVDError::UnknownWriteConstraint(e)
+            | SVDError::MoreThanOneWriteConstraint(e)
+            | SVDError::UnknownUsageVariant(e)
+            | SVDError::NotExpectedTag(e, _)
+            | SVDError::InvalidRegisterCluster(e, _)
+            | SVDError::InvalidProtection(e, _)
+            | SVDError::InvalidModifiedWriteValues(e, _)
+            | SVDError::InvalidReadAction(e, _)
+            | SVDError::InvalidDataType(e, _)
+            | SVDError::InvalidBooleanValue(e, _, _)
+            | SVDError::DimIndexLength(e, _, _) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 // TODO: Consider making into an Error
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum InvalidBitRange {
@@ -63,6 +197,8 @@ pub enum InvalidBitRange {
     ParseError,
     MsbLsb,
     Empty,
+    /// The msb is lower than the lsb, or the width overflows
+    Width,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
@@ -71,6 +207,36 @@ pub enum BuildError {
     Uninitialized(String),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DeriveError {
+    #[error("`{0}` is derived from `{1}`, which does not exist")]
+    NotFound(String, String),
+    #[error("`{0}` is derived from `{1}`, which forms a cycle")]
+    Cycle(String, String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum AlternateError {
+    #[error("`{0}` is an alternate of `{1}`, which does not exist")]
+    NotFound(String, String),
+    #[error("`{0}` is an alternate of `{1}`, but their addresses do not overlap")]
+    NotOverlapping(String, String),
+    #[error("`{0}` is an alternate of `{1}`, but their addresses differ")]
+    DifferentAddress(String, String),
+    #[error("`{0}` is in alternate group `{1}`, but no other register has its address")]
+    NothingToAlternate(String, String),
+    #[error("`{0}` and `{1}` overlap, but neither is declared as an alternate")]
+    Undeclared(String, String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DimIndexError {
+    #[error("`{0}` is not a valid dimIndex")]
+    Invalid(String),
+    #[error("dimIndex range `{0}` ends before it starts")]
+    ReversedRange(String),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
 pub enum NameError {
     #[error("Name `{0}` in tag `{1}` contains unexpected symbol")]
@@ -89,6 +255,260 @@ pub enum ResetValueError {
     MaskTooLarge(u64, u32),
 }
 
+/// Error returned by [`parse`](crate::parse), [`encode`](crate::encode) and builders.
+///
+/// It keeps the chain of contexts of `anyhow::Error`. `{}` prints the reason and
+/// the location of the error, `{:#}` prints all contexts. It gives access to the typed cause with [`Error::kind`]
+pub struct Error(anyhow::Error);
+
+/// Typed cause of an [`Error`]
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum ErrorKind<'a> {
+    /// The document is not valid XML
+    Xml(&'a xmltree::ParseError),
+    Svd(&'a SVDError),
+    Build(&'a BuildError),
+    Name(&'a NameError),
+    ResetValue(&'a ResetValueError),
+    DimIndex(&'a DimIndexError),
+    Derive(&'a DeriveError),
+    Alternate(&'a AlternateError),
+    EnumeratedValue(&'a crate::svd::enumeratedvalue::EnumeratedValueError),
+    EnumeratedValues(&'a crate::svd::enumeratedvalues::EnumeratedValuesError),
+    Peripheral(&'a crate::svd::peripheralinfo::PeripheralError),
+    SauRegion(&'a crate::svd::sauregions::SauRegionError),
+    /// Any other error, e.g. a number which could not be parsed
+    Other,
+}
+
+/// Element named in the context of an [`Error`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathElement {
+    /// XML tag of the element, e.g. `register`
+    pub tag: &'static str,
+    /// Name of the element
+    pub name: String,
+}
+
+impl core::fmt::Display for PathElement {
+    /// Prints the kind of the element and its name, e.g. ``enumerated value `ON` ``
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let kind = match self.tag {
+            "enumeratedValue" => "enumerated value",
+            "enumeratedValues" => "enumerated values",
+            tag => tag,
+        };
+        write!(f, "{} `{}`", kind, self.name)
+    }
+}
+
+/// Error context with the elements which were being parsed, outermost first.
+///
+/// It is displayed as ``In peripheral `TIMER0`, register `CR` ``
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElementPath(pub Vec<PathElement>);
+
+impl core::fmt::Display for ElementPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("In ")?;
+        for (i, elem) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", elem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the element being parsed to the [`ElementPath`] context of an error
+pub(crate) trait InElement<T> {
+    /// Puts the element with `tag` and `name` in front of the path of the error
+    fn in_element(self, tag: &'static str, name: &str) -> Result<T>;
+}
+
+impl<T> InElement<T> for Result<T> {
+    fn in_element(self, tag: &'static str, name: &str) -> Result<T> {
+        self.map_err(|mut err| {
+            let elem = PathElement {
+                tag,
+                name: name.to_string(),
+            };
+            match err.downcast_mut::<ElementPath>() {
+                Some(path) => {
+                    path.0.insert(0, elem);
+                    err
+                }
+                None => err.context(ElementPath(vec![elem])),
+            }
+        })
+    }
+}
+
+/// Returns the elements of the [`ElementPath`] contexts of `err`
+/// and of the errors nested in it, outermost first
+pub(crate) fn element_path(err: &anyhow::Error) -> Vec<PathElement> {
+    layers(err)
+        .into_iter()
+        .filter_map(|e| e.downcast_ref::<ElementPath>())
+        .flat_map(|p| p.0.iter().cloned())
+        .collect()
+}
+
+// The error and the errors nested in it, outermost first
+fn layers(err: &anyhow::Error) -> Vec<&anyhow::Error> {
+    let mut layers = vec![err];
+    let mut current = err;
+    while let Some(inner) = current.chain().find_map(|e| e.downcast_ref::<Error>()) {
+        layers.push(&inner.0);
+        current = &inner.0;
+    }
+    layers
+}
+
+impl Error {
+    /// Returns the typed cause of the error
+    pub fn kind(&self) -> ErrorKind<'_> {
+        let inner = self.layers().pop().unwrap_or(&self.0);
+        if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Svd(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Build(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Name(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::ResetValue(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::DimIndex(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Derive(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Alternate(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::EnumeratedValue(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::EnumeratedValues(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Peripheral(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::SauRegion(e)
+        } else if let Some(e) = inner.downcast_ref() {
+            ErrorKind::Xml(e)
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    /// Returns the elements which were being parsed, outermost first
+    pub fn path(&self) -> Vec<PathElement> {
+        element_path(&self.0)
+    }
+
+    /// Returns the location in the source of the element which caused the error
+    pub fn location(&self) -> Option<&crate::position::SourceLocation> {
+        self.downcast_ref()
+    }
+
+    /// Returns the outermost error or context of type `E`
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+    {
+        self.layers().into_iter().find_map(|e| e.downcast_ref())
+    }
+
+    /// Iterates over the error and its causes, outermost first
+    pub fn chain(&self) -> anyhow::Chain<'_> {
+        self.0.chain()
+    }
+
+    /// Wraps the error with additional context
+    pub fn context<C>(self, context: C) -> Self
+    where
+        C: core::fmt::Display + Send + Sync + 'static,
+    {
+        Self(self.0.context(context))
+    }
+
+    /// Returns the underlying `anyhow::Error`
+    pub fn into_anyhow(self) -> anyhow::Error {
+        self.0
+    }
+
+    pub(crate) fn as_anyhow(&self) -> &anyhow::Error {
+        &self.0
+    }
+
+    pub(crate) fn as_anyhow_mut(&mut self) -> &mut anyhow::Error {
+        &mut self.0
+    }
+
+    // The error and the errors nested in it, outermost first
+    fn layers(&self) -> Vec<&anyhow::Error> {
+        layers(&self.0)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.0)
+        } else {
+            write!(f, "{}", self.0.root_cause())?;
+            match self.location() {
+                Some(location) => write!(f, "\n{}", location),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self(e)
+    }
+}
+
+macro_rules! from_error {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Error {
+                fn from(e: $ty) -> Self {
+                    Self(e.into())
+                }
+            }
+        )*
+    };
+}
+
+from_error!(
+    xmltree::ParseError,
+    SVDError,
+    BuildError,
+    NameError,
+    ResetValueError,
+    DimIndexError,
+    DeriveError,
+    AlternateError,
+    crate::svd::enumeratedvalue::EnumeratedValueError,
+    crate::svd::enumeratedvalues::EnumeratedValuesError,
+    crate::svd::peripheralinfo::PeripheralError,
+    crate::svd::sauregions::SauRegionError
+);
+
 #[cfg(feature = "strict")]
 pub(crate) fn check_name(name: &str, tag: &str) -> Result<()> {
     static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new("^[_A-Za-z0-9]*$").unwrap());
@@ -158,13 +578,15 @@ pub(crate) fn check_reset_value(
     Ok(())
 }
 
-#[cfg(feature = "strict")]
 #[cfg(test)]
 mod tests {
-    use crate::error::check_reset_value;
+    use crate::error::{BuildError, ErrorKind, SVDError};
 
+    #[cfg(feature = "strict")]
     #[test]
     fn test_check_reset_value() {
+        use crate::error::check_reset_value;
+
         check_reset_value(None, None, None).unwrap();
         check_reset_value(Some(8), None, None).unwrap();
         check_reset_value(Some(8), None, Some(0xff)).unwrap();
@@ -184,4 +606,96 @@ mod tests {
             "value should conflict with mask"
         );
     }
+
+    #[test]
+    fn error_kind() {
+        let err = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <peripherals>
+                    <peripheral>
+                        <name>TIMER0</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <access>read-only-ish</access>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap_err();
+        match err.kind() {
+            ErrorKind::Svd(SVDError::UnknownAccessType(elem, access)) => {
+                assert_eq!(access, "read-only-ish");
+                assert_eq!(elem.tag, "access");
+                assert_eq!(elem.path, err.path());
+                assert_eq!(elem.span.unwrap().line, 12);
+            }
+            kind => panic!("unexpected {:?}", kind),
+        }
+        let path: Vec<_> = err.path().into_iter().map(|p| (p.tag, p.name)).collect();
+        assert_eq!(
+            path,
+            vec![
+                ("device", "TEST".to_string()),
+                ("peripheral", "TIMER0".to_string()),
+                ("register", "CR".to_string()),
+            ]
+        );
+        assert_eq!(err.location().unwrap().span.line, 12);
+        assert!(
+            format!("{:#}", err).contains("In device `TEST`, peripheral `TIMER0`, register `CR`: ")
+        );
+        let msg = err.to_string();
+        assert!(msg.starts_with("unknown access variant 'read-only-ish' found\nat line 12"));
+
+        let err = crate::svd::registerinfo::RegisterInfoBuilder::default()
+            .build()
+            .unwrap_err();
+        match err.kind() {
+            ErrorKind::Build(BuildError::Uninitialized(_)) => {}
+            kind => panic!("unexpected {:?}", kind),
+        }
+
+        let err = crate::parse("<device>").unwrap_err();
+        match err.kind() {
+            ErrorKind::Xml(_) => {}
+            kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn element_ref_in_copies() {
+        use crate::error::ElementRef;
+        use xmltree::{Element, XMLNode};
+
+        let tree = Element::parse(
+            "<registers>
+                <register><name>CR</name><access>read-only-ish</access></register>
+                <register><name>SR</name><access>read-only-ish</access></register>
+            </registers>"
+                .as_bytes(),
+        )
+        .unwrap();
+        let registers: Vec<_> = tree
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .collect();
+        let elem = ElementRef::new(registers[0]);
+        let copy = tree.clone();
+        let copies: Vec<_> = copy
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .collect();
+        assert!(elem.is(copies[0]));
+        assert!(!elem.is(copies[1]));
+    }
 }
@@ -23,7 +23,7 @@
 #![deny(warnings)]
 
 use std::collections::HashMap;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 // ElementExt extends XML elements with useful methods
 pub mod elementext;
@@ -33,6 +33,7 @@ pub use svd::*;
 // Error defines SVD error types
 pub mod error;
 use anyhow::Result;
+pub use error::Error;
 // Parse defines parsing interfaces
 pub mod parse;
 use parse::Parse;
@@ -42,20 +43,44 @@ use encode::Encode;
 // Types defines simple types and parse/encode implementations
 pub mod types;
 
-#[cfg(feature = "derive-from")]
+// DeriveFrom defines `derivedFrom` merging and resolution
 pub mod derive_from;
-#[cfg(feature = "derive-from")]
 pub use derive_from::DeriveFrom;
+// Address computes absolute addresses of registers
+pub mod address;
+// Path defines iterators over elements together with their enclosing elements
+pub mod path;
+// Position locates elements in the SVD source for error reporting
+pub mod position;
+// Diagnostic collects all errors of an SVD file instead of stopping at the first one
+pub mod diagnostic;
+pub use diagnostic::parse_with_diagnostics;
 
 /// Parses the contents of an SVD (XML) string
-pub fn parse(xml: &str) -> Result<Device> {
+pub fn parse(xml: &str) -> Result<Device, Error> {
+    parse_with_config(xml, &parse::Config::default())
+}
+
+/// Parses the contents of an SVD (XML) string, running the vendor extension
+/// parsers registered in `config` on the vendor extensions of every element.
+///
+/// Errors carry the [`SourceLocation`](position::SourceLocation) of the
+/// offending element when it can be found
+pub fn parse_with_config(xml: &str, config: &parse::Config) -> Result<Device, Error> {
     let xml = trim_utf8_bom(xml);
     let tree = Element::parse(xml.as_bytes())?;
-    Device::parse(&tree)
+    let mut device = Device::parse(&tree).map_err(|e| position::locate(xml, &tree, e))?;
+    match svd::vendorextensions::parse_extensions(&mut device, config)
+        .into_iter()
+        .next()
+    {
+        Some(e) => Err(position::locate(xml, &tree, e).into()),
+        None => Ok(device),
+    }
 }
 
 /// Encodes a device object to an SVD (XML) string
-pub fn encode(d: &Device) -> Result<String> {
+pub fn encode(d: &Device) -> Result<String, Error> {
     let root = d.encode()?;
     let mut wr = Vec::new();
     root.write(&mut wr).unwrap();
@@ -79,8 +104,7 @@ pub(crate) fn new_element(name: &str, text: Option<String>) -> Element {
         namespaces: None,
         name: String::from(name),
         attributes: HashMap::new(),
-        children: Vec::new(),
-        text,
+        children: text.map(XMLNode::Text).into_iter().collect(),
     }
 }
 
@@ -96,24 +120,26 @@ pub fn run_test<
 >(
     tests: &[(T, &str)],
 ) {
+    // Hack to make assert be order agnostic
+    fn sort(children: &mut [XMLNode]) {
+        let name = |n: &XMLNode| n.as_element().map(|e| e.name.clone());
+        children.sort_by_key(name);
+        children.iter_mut().for_each(|n| {
+            if let Some(e) = n.as_mut_element() {
+                e.children.sort_by_key(name);
+            }
+        });
+    }
     for t in tests {
         let mut tree1 = Element::parse(t.1.as_bytes()).unwrap();
         let elem = T::parse(&tree1).unwrap();
-        // Hack to make assert be order agnostic
-        tree1.children.sort_by(|e1, e2| e1.name.cmp(&e2.name));
-        tree1.children.iter_mut().for_each(|e| {
-            e.children.sort_by(|e1, e2| e1.name.cmp(&e2.name));
-        });
+        sort(&mut tree1.children);
         assert_eq!(
             elem, t.0,
             "Error parsing xml` (mismatch between parsed and expected)"
         );
         let mut tree2 = elem.encode().unwrap();
-        // Hack to make assert be order agnostic
-        tree2.children.sort_by(|e1, e2| e1.name.cmp(&e2.name));
-        tree2.children.iter_mut().for_each(|e| {
-            e.children.sort_by(|e1, e2| e1.name.cmp(&e2.name));
-        });
+        sort(&mut tree2.children);
         assert_eq!(
             tree1, tree2,
             "Error encoding xml (mismatch between encoded and original)"
@@ -121,6 +147,27 @@ pub fn run_test<
     }
 }
 
+/// Checks that items are encoded with their children in the order of the xml,
+/// which `run_test` ignores
+#[cfg(test)]
+pub fn run_order_test<T: Encode<Error = anyhow::Error>>(tests: &[(T, &str)]) {
+    let names = |elem: &Element| -> Vec<String> {
+        elem.children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .map(|e| e.name.clone())
+            .collect()
+    };
+    for t in tests {
+        let tree = Element::parse(t.1.as_bytes()).unwrap();
+        assert_eq!(
+            names(&t.0.encode().unwrap()),
+            names(&tree),
+            "Error encoding xml (mismatch between encoded and original order)"
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
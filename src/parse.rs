@@ -1,8 +1,14 @@
 //! Parse traits.
 //! These support parsing of SVD types from XML
 
+use std::sync::Arc;
+
+use anyhow::Context;
 use xmltree::Element;
 
+use crate::svd::vendorextensions::TypedExtension;
+use crate::svd::VendorExtension;
+
 /// Parse trait allows SVD objects to be parsed from XML elements.
 pub trait Parse {
     /// Object returned by parse method
@@ -30,3 +36,24 @@ where
         Err(e) => Err(e),
     }
 }
+
+type ExtensionParser = fn(&Element) -> anyhow::Result<TypedExtension>;
+
+/// Parser configuration used by [`parse_with_config`](crate::parse_with_config)
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct Config {
+    pub(crate) extensions: Vec<ExtensionParser>,
+}
+
+impl Config {
+    /// Registers a parser for the vendor extension `T`
+    pub fn extension<T: VendorExtension>(mut self) -> Self {
+        self.extensions.push(|tree| {
+            let ext = T::parse(tree)
+                .with_context(|| format!("In vendor extension `{}`", std::any::type_name::<T>()))?;
+            Ok(Arc::new(ext))
+        });
+        self
+    }
+}
@@ -0,0 +1,410 @@
+//! Path-aware iterators.
+//! These iterators walk registers, clusters and fields together with the elements enclosing them
+
+use crate::derive_from::DeriveFrom;
+use crate::svd::{
+    Cluster, Device, Field, Peripheral, Register, RegisterCluster, RegisterProperties,
+};
+
+/// Register together with the elements enclosing it
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterPath<'a> {
+    /// Peripheral containing the register, if iteration started from a peripheral or device
+    pub peripheral: Option<&'a Peripheral>,
+
+    /// Clusters enclosing the register, outermost first
+    pub clusters: Vec<&'a Cluster>,
+
+    pub register: &'a Register,
+
+    /// Address offset of the register relative to the start of iteration
+    /// (the peripheral base address when iterating a peripheral or device).
+    /// For arrays this is the offset of the first element
+    pub offset: u64,
+
+    /// Register properties inherited from the enclosing elements
+    pub properties: RegisterProperties,
+}
+
+/// Cluster together with the elements enclosing it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterPath<'a> {
+    /// Peripheral containing the cluster, if iteration started from a peripheral or device
+    pub peripheral: Option<&'a Peripheral>,
+
+    /// Clusters enclosing the cluster, outermost first
+    pub clusters: Vec<&'a Cluster>,
+
+    pub cluster: &'a Cluster,
+
+    /// Address offset of the cluster relative to the start of iteration.
+    /// For arrays this is the offset of the first element
+    pub offset: u64,
+
+    /// Register properties inherited by the registers of the cluster
+    pub properties: RegisterProperties,
+}
+
+/// Field together with the elements enclosing it
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldPath<'a> {
+    /// Register containing the field
+    pub register: RegisterPath<'a>,
+
+    pub field: &'a Field,
+}
+
+fn names<'a>(
+    peripheral: Option<&'a Peripheral>,
+    clusters: &[&'a Cluster],
+    name: &'a str,
+) -> Vec<&'a str> {
+    peripheral
+        .map(|p| p.name.as_str())
+        .into_iter()
+        .chain(clusters.iter().map(|c| c.name.as_str()))
+        .chain(Some(name))
+        .collect()
+}
+
+impl<'a> RegisterPath<'a> {
+    /// Returns the dotted path of the register, e.g. `PERIPH.CLUSTER.REG`
+    pub fn path_name(&self) -> String {
+        names(self.peripheral, &self.clusters, &self.register.name).join(".")
+    }
+
+    /// Returns absolute address of the register when the peripheral is known
+    pub fn address(&self) -> Option<u64> {
+        self.peripheral
+            .and_then(|p| p.base_address.checked_add(self.offset))
+    }
+}
+
+impl<'a> ClusterPath<'a> {
+    /// Returns the dotted path of the cluster, e.g. `PERIPH.CLUSTER`
+    pub fn path_name(&self) -> String {
+        names(self.peripheral, &self.clusters, &self.cluster.name).join(".")
+    }
+
+    /// Returns absolute address of the cluster when the peripheral is known
+    pub fn address(&self) -> Option<u64> {
+        self.peripheral
+            .and_then(|p| p.base_address.checked_add(self.offset))
+    }
+}
+
+impl<'a> FieldPath<'a> {
+    /// Returns the dotted path of the field, e.g. `PERIPH.CLUSTER.REG.FIELD`
+    pub fn path_name(&self) -> String {
+        format!("{}.{}", self.register.path_name(), self.field.name)
+    }
+}
+
+/// Register or cluster with the elements enclosing it
+enum Item<'a> {
+    Register(RegisterPath<'a>),
+    Cluster(ClusterPath<'a>),
+}
+
+struct Entry<'a> {
+    node: &'a RegisterCluster,
+    clusters: Vec<&'a Cluster>,
+    offset: u64,
+    properties: RegisterProperties,
+}
+
+/// Depth-first walk over registers and clusters
+struct Walk<'a> {
+    peripheral: Option<&'a Peripheral>,
+    rem: Vec<Entry<'a>>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(
+        peripheral: Option<&'a Peripheral>,
+        children: &'a [RegisterCluster],
+        properties: RegisterProperties,
+    ) -> Self {
+        let rem = children
+            .iter()
+            .rev()
+            .map(|node| Entry {
+                node,
+                clusters: Vec::new(),
+                offset: 0,
+                properties,
+            })
+            .collect();
+        Self { peripheral, rem }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Item<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.rem.pop()?;
+        Some(match entry.node {
+            RegisterCluster::Register(register) => Item::Register(RegisterPath {
+                peripheral: self.peripheral,
+                clusters: entry.clusters,
+                register,
+                offset: entry.offset + u64::from(register.address_offset),
+                properties: register.properties().derive_from(&entry.properties),
+            }),
+            RegisterCluster::Cluster(cluster) => {
+                let offset = entry.offset + u64::from(cluster.address_offset);
+                let properties = cluster
+                    .default_register_properties
+                    .derive_from(&entry.properties);
+                let mut clusters = entry.clusters.clone();
+                clusters.push(cluster);
+                for node in cluster.children.iter().rev() {
+                    self.rem.push(Entry {
+                        node,
+                        clusters: clusters.clone(),
+                        offset,
+                        properties,
+                    });
+                }
+                Item::Cluster(ClusterPath {
+                    peripheral: self.peripheral,
+                    clusters: entry.clusters,
+                    cluster,
+                    offset,
+                    properties,
+                })
+            }
+        })
+    }
+}
+
+/// Iterator over registers with the elements enclosing them
+pub struct RegPathIter<'a> {
+    walk: Walk<'a>,
+}
+
+impl<'a> RegPathIter<'a> {
+    pub(crate) fn new(
+        peripheral: Option<&'a Peripheral>,
+        children: &'a [RegisterCluster],
+        properties: RegisterProperties,
+    ) -> Self {
+        Self {
+            walk: Walk::new(peripheral, children, properties),
+        }
+    }
+}
+
+impl<'a> Iterator for RegPathIter<'a> {
+    type Item = RegisterPath<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Item::Register(r) = self.walk.next()? {
+                return Some(r);
+            }
+        }
+    }
+}
+
+/// Iterator over clusters with the elements enclosing them
+pub struct ClusterPathIter<'a> {
+    walk: Walk<'a>,
+}
+
+impl<'a> ClusterPathIter<'a> {
+    pub(crate) fn new(
+        peripheral: Option<&'a Peripheral>,
+        children: &'a [RegisterCluster],
+        properties: RegisterProperties,
+    ) -> Self {
+        Self {
+            walk: Walk::new(peripheral, children, properties),
+        }
+    }
+}
+
+impl<'a> Iterator for ClusterPathIter<'a> {
+    type Item = ClusterPath<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Item::Cluster(c) = self.walk.next()? {
+                return Some(c);
+            }
+        }
+    }
+}
+
+/// Iterator over fields with the elements enclosing them
+pub struct FieldPathIter<'a> {
+    registers: RegPathIter<'a>,
+    current: Option<(RegisterPath<'a>, core::slice::Iter<'a, Field>)>,
+}
+
+impl<'a> FieldPathIter<'a> {
+    pub(crate) fn new(
+        peripheral: Option<&'a Peripheral>,
+        children: &'a [RegisterCluster],
+        properties: RegisterProperties,
+    ) -> Self {
+        Self {
+            registers: RegPathIter::new(peripheral, children, properties),
+            current: None,
+        }
+    }
+}
+
+impl<'a> Iterator for FieldPathIter<'a> {
+    type Item = FieldPath<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((register, fields)) = self.current.as_mut() {
+                if let Some(field) = fields.next() {
+                    return Some(FieldPath {
+                        register: register.clone(),
+                        field,
+                    });
+                }
+            }
+            let register = self.registers.next()?;
+            let fields = register.register.fields.as_deref().unwrap_or(&[]).iter();
+            self.current = Some((register, fields));
+        }
+    }
+}
+
+fn peripheral_properties(device: &Device, peripheral: &Peripheral) -> RegisterProperties {
+    peripheral
+        .default_register_properties
+        .derive_from(&device.default_register_properties)
+}
+
+fn peripheral_registers(peripheral: &Peripheral) -> &[RegisterCluster] {
+    peripheral.registers.as_deref().unwrap_or(&[])
+}
+
+/// Returns an iterator over the registers of `peripheral` of `device`
+pub(crate) fn peripheral_reg_path_iter<'a>(
+    device: &Device,
+    peripheral: &'a Peripheral,
+) -> RegPathIter<'a> {
+    RegPathIter::new(
+        Some(peripheral),
+        peripheral_registers(peripheral),
+        peripheral_properties(device, peripheral),
+    )
+}
+
+/// Returns an iterator over the registers of all peripherals of `device`
+pub fn device_reg_path_iter(device: &Device) -> impl Iterator<Item = RegisterPath<'_>> {
+    device
+        .peripherals
+        .iter()
+        .flat_map(move |p| peripheral_reg_path_iter(device, p))
+}
+
+/// Returns an iterator over the clusters of all peripherals of `device`
+pub fn device_cluster_path_iter(device: &Device) -> impl Iterator<Item = ClusterPath<'_>> {
+    device.peripherals.iter().flat_map(move |p| {
+        ClusterPathIter::new(
+            Some(p),
+            peripheral_registers(p),
+            peripheral_properties(device, p),
+        )
+    })
+}
+
+/// Returns an iterator over the fields of all peripherals of `device`
+pub fn device_field_path_iter(device: &Device) -> impl Iterator<Item = FieldPath<'_>> {
+    device.peripherals.iter().flat_map(move |p| {
+        FieldPathIter::new(
+            Some(p),
+            peripheral_registers(p),
+            peripheral_properties(device, p),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::svd::Access;
+
+    #[test]
+    fn paths() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>TIMER0</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <access>read-only</access>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>EN</name>
+                                        <bitOffset>0</bitOffset>
+                                        <bitWidth>1</bitWidth>
+                                    </field>
+                                </fields>
+                            </register>
+                            <cluster>
+                                <name>CH%s</name>
+                                <addressOffset>0x10</addressOffset>
+                                <dim>2</dim>
+                                <dimIncrement>0x10</dimIncrement>
+                                <size>16</size>
+                                <cluster>
+                                    <name>INNER</name>
+                                    <addressOffset>0x4</addressOffset>
+                                    <register>
+                                        <name>CNT</name>
+                                        <addressOffset>0x2</addressOffset>
+                                        <fields>
+                                            <field>
+                                                <name>VAL</name>
+                                                <bitOffset>0</bitOffset>
+                                                <bitWidth>16</bitWidth>
+                                            </field>
+                                        </fields>
+                                    </register>
+                                </cluster>
+                            </cluster>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let regs: Vec<_> = device.reg_path_iter().collect();
+        assert_eq!(regs.len(), 2);
+        assert_eq!(regs[0].path_name(), "TIMER0.CR");
+        assert_eq!(regs[0].address(), Some(0x40000000));
+        assert_eq!(regs[0].properties.size, Some(32));
+        assert_eq!(regs[1].path_name(), "TIMER0.CH%s.INNER.CNT");
+        assert_eq!(regs[1].offset, 0x16);
+        assert_eq!(regs[1].properties.size, Some(16));
+        assert_eq!(regs[1].properties.access, Some(Access::ReadOnly));
+
+        let clusters: Vec<_> = device.peripherals[0]
+            .cluster_path_iter()
+            .map(|c| (c.path_name(), c.offset))
+            .collect();
+        assert_eq!(
+            clusters,
+            vec![
+                ("TIMER0.CH%s".to_string(), 0x10),
+                ("TIMER0.CH%s.INNER".to_string(), 0x14)
+            ]
+        );
+
+        let fields: Vec<_> = device.field_path_iter().map(|f| f.path_name()).collect();
+        assert_eq!(fields, vec!["TIMER0.CR.EN", "TIMER0.CH%s.INNER.CNT.VAL"]);
+    }
+}
@@ -0,0 +1,329 @@
+//! Source positions.
+//! This module locates XML elements in the SVD source so that parse errors can point at them
+
+use core::fmt;
+
+use xml::common::Position;
+use xml::reader::{EventReader, XmlEvent};
+use xmltree::{Element, XMLNode};
+
+use crate::error::{element_path, Error, PathElement, SVDError};
+
+/// Location of an element in the SVD source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// Line of the start tag, counting from 1
+    pub line: usize,
+    /// Column of the start tag in characters, counting from 1
+    pub column: usize,
+    /// Byte offset of the start tag
+    pub start: usize,
+    /// Byte offset just after the end tag
+    pub end: usize,
+}
+
+/// Error context with the location of the element which failed to parse.
+///
+/// It is attached to errors returned by [`parse`](crate::parse) and can be
+/// retrieved with `error.downcast_ref::<SourceLocation>()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Location of the element
+    pub span: Span,
+    /// Source line of the start tag
+    pub line: String,
+}
+
+impl fmt::Display for SourceLocation {
+    /// Prints the line of the element with a caret under its start tag
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let number = self.span.line.to_string();
+        let margin = " ".repeat(number.len());
+        let indent: String = self
+            .line
+            .chars()
+            .take(self.span.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = (self.span.end - self.span.start)
+            .min(self.line.len().saturating_sub(indent.len()))
+            .max(1);
+        writeln!(f, "at line {}, column {}", self.span.line, self.span.column)?;
+        writeln!(f, "{} |", margin)?;
+        writeln!(f, "{} | {}", number, self.line)?;
+        write!(f, "{} | {}{}", margin, indent, "^".repeat(width))
+    }
+}
+
+/// Spans of all elements of a document, in document order
+pub(crate) struct SourceMap<'a> {
+    source: &'a str,
+    spans: Vec<Span>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Reads the element positions of `source`. Elements after an XML error have no span
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts: Vec<usize> = core::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        // Converts a position of the XML reader into a byte offset,
+        // continuing from the previous position as they only move forward
+        let mut last = (0, 0, 0);
+        let mut offset = |reader: &EventReader<&[u8]>| {
+            let pos = reader.position();
+            let (row, column) = (pos.row as usize, pos.column as usize);
+            let (start, skip) = if row == last.0 && column >= last.1 {
+                (last.2, column - last.1)
+            } else {
+                let start = line_starts.get(row).copied().unwrap_or(source.len());
+                (start, column)
+            };
+            let offset = source[start..]
+                .char_indices()
+                .nth(skip)
+                .map(|(i, _)| start + i)
+                .unwrap_or(source.len());
+            last = (row, column, offset);
+            offset
+        };
+
+        let mut spans = Vec::new();
+        let mut open = Vec::new();
+        let mut reader = EventReader::new(source.as_bytes());
+        loop {
+            match reader.next() {
+                Ok(XmlEvent::StartElement { .. }) => {
+                    // The reader may report a position past the `<` of the
+                    // first element, so look back for it
+                    let start = offset(&reader);
+                    let start = source[..start]
+                        .rfind('<')
+                        .filter(|_| !source[start..].starts_with('<'))
+                        .unwrap_or(start);
+                    let line = match line_starts.binary_search(&start) {
+                        Ok(i) => i,
+                        Err(i) => i - 1,
+                    };
+                    open.push(spans.len());
+                    spans.push(Span {
+                        line: line + 1,
+                        column: source[line_starts[line]..start].chars().count() + 1,
+                        start,
+                        end: start,
+                    });
+                }
+                Ok(XmlEvent::EndElement { .. }) => {
+                    let end = offset(&reader);
+                    if let Some(i) = open.pop() {
+                        spans[i].end = source[end..]
+                            .find('>')
+                            .map(|e| end + e + 1)
+                            .unwrap_or(source.len());
+                    }
+                }
+                Ok(XmlEvent::EndDocument) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        Self { source, spans }
+    }
+
+    /// Returns the location of `elem`, which must be a node of `root`
+    pub(crate) fn location(&self, root: &Element, elem: &Element) -> Option<SourceLocation> {
+        let span = *self.spans.get(index_of(root, elem)?)?;
+        let line_start = self.source[..span.start]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let line = self.source[line_start..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Some(SourceLocation { span, line })
+    }
+}
+
+/// Returns the index of `elem` among the nodes of `root` in document order
+fn index_of(root: &Element, elem: &Element) -> Option<usize> {
+    fn walk(node: &Element, elem: &Element, index: &mut usize) -> bool {
+        if core::ptr::eq(node, elem) {
+            return true;
+        }
+        *index += 1;
+        node.children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .any(|c| walk(c, elem, index))
+    }
+    let mut index = 0;
+    if walk(root, elem, &mut index) {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+/// Returns the first node of `root` in document order matching `f`
+fn find<'a>(root: &'a Element, f: &dyn Fn(&Element) -> bool) -> Option<&'a Element> {
+    if f(root) {
+        return Some(root);
+    }
+    root.children
+        .iter()
+        .filter_map(XMLNode::as_element)
+        .find_map(|c| find(c, f))
+}
+
+/// Finds the element of `tree` which caused `err`.
+///
+/// This is the element held by the `SVDError`, if any, searched inside the innermost
+/// element named in the error contexts, otherwise that element itself.
+/// `tree` may be a copy of the tree which was parsed
+pub(crate) fn error_element<'a>(tree: &'a Element, err: &anyhow::Error) -> Option<&'a Element> {
+    let scope = find_path(tree, &element_path(err));
+    if let Some(payload) = svd_error(err).and_then(SVDError::element) {
+        if let Some(elem) = find(scope.unwrap_or(tree), &|e| payload.is(e)) {
+            return Some(elem);
+        }
+    }
+    scope
+}
+
+/// Finds the innermost element of `path` in `tree`, following it down from the root.
+/// Elements of the path which are not found are skipped
+pub(crate) fn find_path<'a>(tree: &'a Element, path: &[PathElement]) -> Option<&'a Element> {
+    let mut found = None;
+    let mut scope = tree;
+    for step in path {
+        let elem = find(scope, &|e| {
+            e.name == step.tag
+                && e.get_child("name").and_then(|n| n.get_text()).as_deref()
+                    == Some(step.name.as_str())
+        });
+        if let Some(elem) = elem {
+            scope = elem;
+            found = Some(elem);
+        }
+    }
+    found
+}
+
+/// Returns the `SVDError` at the bottom of `err`
+fn svd_error(err: &anyhow::Error) -> Option<&SVDError> {
+    match err.chain().find_map(|e| e.downcast_ref::<Error>()) {
+        Some(inner) => svd_error(inner.as_anyhow()),
+        None => err.downcast_ref(),
+    }
+}
+
+/// Mutable version of [`svd_error`]
+fn svd_error_mut(err: &mut anyhow::Error) -> Option<&mut SVDError> {
+    if err.chain().any(|e| e.is::<Error>()) {
+        svd_error_mut(err.downcast_mut::<Error>()?.as_anyhow_mut())
+    } else {
+        err.downcast_mut()
+    }
+}
+
+/// Adds the location of the element which caused `err` in `source`.
+///
+/// The element held by the `SVDError` also gets its path and span
+pub(crate) fn locate(source: &str, tree: &Element, mut err: anyhow::Error) -> anyhow::Error {
+    let location = error_element(tree, &err).and_then(|e| SourceMap::new(source).location(tree, e));
+    let path = element_path(&err);
+    if let Some(elem) = svd_error_mut(&mut err).and_then(SVDError::element_mut) {
+        elem.path = path;
+        elem.span = location.as_ref().map(|l| l.span);
+    }
+    match location {
+        Some(location) => err.context(location),
+        None => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svd(field: &str) -> String {
+        format!(
+            "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIMER0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR</name>
+          <addressOffset>0x0</addressOffset>
+          <fields>
+            <field>
+              <name>EN</name>
+              <bitOffset>0</bitOffset>
+{}
+            </field>
+          </fields>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>",
+            field
+        )
+    }
+
+    #[test]
+    fn element_with_error() {
+        let err = crate::parse(&svd("              <bitWidth>one</bitWidth>")).unwrap_err();
+        let location = err.downcast_ref::<SourceLocation>().unwrap();
+        // the field misses a valid width
+        assert_eq!(location.span.line, 12);
+        assert_eq!(location.span.column, 13);
+        assert_eq!(location.line, "            <field>");
+        assert_eq!(
+            location.to_string(),
+            "at line 12, column 13
+   |
+12 |             <field>
+   |             ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn named_element() {
+        let source = svd("              <bitWidth>1</bitWidth>
+              <enumeratedValues>
+                <enumeratedValue>
+                  <name>ON</name>
+                </enumeratedValue>
+              </enumeratedValues>");
+        let err = crate::parse(&source).unwrap_err();
+        let location = err.downcast_ref::<SourceLocation>().unwrap();
+        // enumerated value without `value` nor `isDefault`
+        assert_eq!(location.span.line, 17);
+        assert_eq!(location.span.column, 17);
+        assert!(source[location.span.start..].starts_with("<enumeratedValue>"));
+    }
+
+    #[test]
+    fn spans() {
+        let source = "<a>\n  <b x=\"1\"/>\n  <c>text</c>\n</a>";
+        let map = SourceMap::new(source);
+        let spans: Vec<_> = map
+            .spans
+            .iter()
+            .map(|s| (s.line, s.column, &source[s.start..s.end]))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (1, 1, source),
+                (2, 3, "<b x=\"1\"/>"),
+                (3, 3, "<c>text</c>"),
+            ]
+        );
+    }
+}
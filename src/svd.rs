@@ -7,6 +7,12 @@ pub use self::endian::Endian;
 pub mod cpu;
 pub use self::cpu::Cpu;
 
+pub mod protection;
+pub use self::protection::Protection;
+
+pub mod sauregions;
+pub use self::sauregions::SauRegionsConfig;
+
 pub mod interrupt;
 pub use self::interrupt::Interrupt;
 
@@ -58,11 +64,26 @@ pub use self::registercluster::RegisterCluster;
 pub mod dimelement;
 pub use self::dimelement::DimElement;
 
+pub mod dimarrayindex;
+pub use self::dimarrayindex::DimArrayIndex;
+
 pub mod peripheral;
 pub use self::peripheral::Peripheral;
 
+pub mod peripheralinfo;
+pub use self::peripheralinfo::PeripheralInfo;
+
 pub mod device;
 pub use self::device::Device;
 
 pub mod modifiedwritevalues;
 pub use self::modifiedwritevalues::ModifiedWriteValues;
+
+pub mod readaction;
+pub use self::readaction::ReadAction;
+
+pub mod datatype;
+pub use self::datatype::DataType;
+
+pub mod vendorextensions;
+pub use self::vendorextensions::{VendorExtension, VendorExtensions};
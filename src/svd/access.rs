@@ -21,7 +21,7 @@ impl Parse for Access {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         match &text[..] {
             "read-only" => Ok(Access::ReadOnly),
@@ -29,7 +29,7 @@ impl Parse for Access {
             "read-writeOnce" => Ok(Access::ReadWriteOnce),
             "write-only" => Ok(Access::WriteOnly),
             "writeOnce" => Ok(Access::WriteOnce),
-            _ => Err(SVDError::UnknownAccessType(tree.clone(), text).into()),
+            _ => Err(SVDError::UnknownAccessType(tree.into(), text).into()),
         }
     }
 }
@@ -57,7 +57,7 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![
+        let tests = [
             (Access::ReadOnly, "<access>read-only</access>"),
             (Access::ReadWrite, "<access>read-write</access>"),
             (Access::ReadWriteOnce, "<access>read-writeOnce</access>"),
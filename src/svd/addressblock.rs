@@ -1,20 +1,67 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::types::Parse;
 
 use crate::encode::Encode;
 use crate::error::*;
 use crate::new_element;
+use crate::parse;
+
+use crate::svd::protection::Protection;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct AddressBlock {
     pub offset: u32,
     pub size: u32,
-    pub usage: String,
+    pub usage: AddressBlockUsage,
+
+    /// Protection of the address block
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub protection: Option<Protection>,
+}
+
+/// Usage of an address block
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressBlockUsage {
+    Registers,
+    Buffer,
+    Reserved,
+}
+
+impl Parse for AddressBlockUsage {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        let text = tree.get_content()?;
+
+        match &text[..] {
+            "registers" => Ok(AddressBlockUsage::Registers),
+            "buffer" => Ok(AddressBlockUsage::Buffer),
+            "reserved" => Ok(AddressBlockUsage::Reserved),
+            _ => Err(SVDError::UnknownUsageVariant(tree.into()).into()),
+        }
+    }
+}
+
+impl Encode for AddressBlockUsage {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let text = match *self {
+            AddressBlockUsage::Registers => "registers",
+            AddressBlockUsage::Buffer => "buffer",
+            AddressBlockUsage::Reserved => "reserved",
+        };
+
+        Ok(new_element("usage", Some(text.to_string())))
+    }
 }
 
 impl Parse for AddressBlock {
@@ -25,7 +72,8 @@ impl Parse for AddressBlock {
         Ok(Self {
             offset: tree.get_child_u32("offset")?,
             size: tree.get_child_u32("size")?,
-            usage: tree.get_child_text("usage")?,
+            usage: AddressBlockUsage::parse(tree.get_child_elem("usage")?)?,
+            protection: parse::optional::<Protection>("protection", tree)?,
         })
     }
 }
@@ -34,18 +82,21 @@ impl Encode for AddressBlock {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
+        let mut children = vec![
+            new_element("offset", Some(format!("{}", self.offset))),
+            new_element("size", Some(format!("0x{:08.x}", self.size))),
+            self.usage.encode()?,
+        ];
+        if let Some(v) = &self.protection {
+            children.push(v.encode()?);
+        }
         Ok(Element {
             prefix: None,
             namespace: None,
             namespaces: None,
             name: String::from("addressBlock"),
             attributes: HashMap::new(),
-            children: vec![
-                new_element("offset", Some(format!("{}", self.offset))),
-                new_element("size", Some(format!("0x{:08.x}", self.size))),
-                new_element("usage", Some(self.usage.clone())),
-            ],
-            text: None,
+            children: children.into_iter().map(XMLNode::Element).collect(),
         })
     }
 }
@@ -57,18 +108,35 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            AddressBlock {
-                offset: 0,
-                size: 0x00000800,
-                usage: String::from("registers"),
-            },
-            "<addressBlock>
+        let tests = [
+            (
+                AddressBlock {
+                    offset: 0,
+                    size: 0x00000800,
+                    usage: AddressBlockUsage::Registers,
+                    protection: None,
+                },
+                "<addressBlock>
                     <offset>0</offset>
                     <size>0x00000800</size>
                     <usage>registers</usage>
                 </addressBlock>",
-        )];
+            ),
+            (
+                AddressBlock {
+                    offset: 0x800,
+                    size: 0x00000100,
+                    usage: AddressBlockUsage::Buffer,
+                    protection: Some(Protection::Secure),
+                },
+                "<addressBlock>
+                    <offset>2048</offset>
+                    <size>0x00000100</size>
+                    <usage>buffer</usage>
+                    <protection>s</protection>
+                </addressBlock>",
+            ),
+        ];
 
         run_test::<AddressBlock>(&tests[..]);
     }
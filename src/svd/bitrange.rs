@@ -42,19 +42,14 @@ impl Parse for BitRange {
             tree.get_child("bitRange")
         {
             let text = range
-                .text
-                .as_ref()
-                .ok_or_else(|| SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Empty))?;
+                .get_text()
+                .ok_or_else(|| SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Empty))?;
             if !text.starts_with('[') {
-                return Err(
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Syntax).into(),
-                );
+                return Err(SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Syntax).into());
                 // TODO: Maybe have a MissingOpen/MissingClosing variant
             }
             if !text.ends_with(']') {
-                return Err(
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Syntax).into(),
-                );
+                return Err(SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Syntax).into());
                 // TODO: Maybe have a MissingOpen/MissingClosing variant
             }
 
@@ -62,21 +57,17 @@ impl Parse for BitRange {
             (
                 parts
                     .next()
-                    .ok_or_else(|| {
-                        SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Syntax)
-                    })?
+                    .ok_or_else(|| SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Syntax))?
                     .parse::<u32>()
                     .with_context(|| {
-                        SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::ParseError)
+                        SVDError::InvalidBitRange(tree.into(), InvalidBitRange::ParseError)
                     })?,
                 parts
                     .next()
-                    .ok_or_else(|| {
-                        SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Syntax)
-                    })?
+                    .ok_or_else(|| SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Syntax))?
                     .parse::<u32>()
                     .with_context(|| {
-                        SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::ParseError)
+                        SVDError::InvalidBitRange(tree.into(), InvalidBitRange::ParseError)
                     })?,
                 BitRangeType::BitRange,
             )
@@ -85,10 +76,10 @@ impl Parse for BitRange {
             (
                 // TODO: `u32::parse` should not hide it's errors
                 u32::parse(msb).with_context(|| {
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::MsbLsb)
+                    SVDError::InvalidBitRange(tree.into(), InvalidBitRange::MsbLsb)
                 })?,
                 u32::parse(lsb).with_context(|| {
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::MsbLsb)
+                    SVDError::InvalidBitRange(tree.into(), InvalidBitRange::MsbLsb)
                 })?,
                 BitRangeType::MsbLsb,
             )
@@ -101,20 +92,24 @@ impl Parse for BitRange {
                 // TODO: capture that error comes from offset/width tag
                 // TODO: `u32::parse` should not hide it's errors
                 offset: u32::parse(offset).with_context(|| {
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::ParseError)
+                    SVDError::InvalidBitRange(tree.into(), InvalidBitRange::ParseError)
                 })?,
                 width: u32::parse(width).with_context(|| {
-                    SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::ParseError)
+                    SVDError::InvalidBitRange(tree.into(), InvalidBitRange::ParseError)
                 })?,
                 range_type: BitRangeType::OffsetWidth,
             });
         } else {
-            return Err(SVDError::InvalidBitRange(tree.clone(), InvalidBitRange::Syntax).into());
+            return Err(SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Syntax).into());
         };
 
+        let width = end
+            .checked_sub(start)
+            .and_then(|w| w.checked_add(1))
+            .ok_or_else(|| SVDError::InvalidBitRange(tree.into(), InvalidBitRange::Width))?;
         Ok(Self {
             offset: start,
-            width: end - start + 1,
+            width,
             range_type,
         })
     }
@@ -143,10 +138,11 @@ impl BitRange {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use xmltree::XMLNode;
 
     #[test]
     fn decode_encode() {
-        let types = vec![
+        let types = [
             (
                 BitRange {
                     offset: 16,
@@ -190,8 +186,28 @@ mod tests {
             let value = BitRange::parse(&tree1).unwrap();
             assert_eq!(value, a, "Parsing `{}` expected `{:?}`", s, a);
             let mut tree2 = new_element("fake", None);
-            tree2.children = value.encode().unwrap();
+            tree2.children = value
+                .encode()
+                .unwrap()
+                .into_iter()
+                .map(XMLNode::Element)
+                .collect();
             assert_eq!(tree1, tree2, "Encoding {:?} expected {}", a, s);
         }
     }
+
+    #[test]
+    fn reversed() {
+        for s in &[
+            "<fake><bitRange>[0:9]</bitRange></fake>",
+            "<fake><lsb>0</lsb><msb>4294967295</msb></fake>",
+        ] {
+            let tree = Element::parse(s.as_bytes()).unwrap();
+            let err = BitRange::parse(&tree).unwrap_err();
+            match err.downcast_ref::<SVDError>() {
+                Some(SVDError::InvalidBitRange(_, InvalidBitRange::Width)) => {}
+                _ => panic!("unexpected {:?}", err),
+            }
+        }
+    }
 }
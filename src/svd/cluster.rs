@@ -1,3 +1,4 @@
+use core::convert::TryFrom;
 use core::ops::{Deref, DerefMut};
 use xmltree::Element;
 
@@ -6,7 +7,11 @@ use crate::types::Parse;
 use crate::elementext::ElementExt;
 use crate::encode::Encode;
 use crate::error::*;
-use crate::svd::{clusterinfo::ClusterInfo, dimelement::DimElement};
+use crate::svd::{
+    clusterinfo::ClusterInfo,
+    dimelement::{expand_name, DimElement},
+    registercluster::RegisterCluster,
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -35,23 +40,75 @@ impl DerefMut for Cluster {
     }
 }
 
+impl Cluster {
+    /// Returns one `Single` cluster per element of the array,
+    /// with all arrays inside the cluster expanded as well.
+    /// A `Single` cluster is returned with its children expanded.
+    /// Expansion stops at the first element whose address offset overflows
+    pub fn expand(&self) -> Vec<Cluster> {
+        let expand_children = |mut info: ClusterInfo| {
+            info.children = info
+                .children
+                .iter()
+                .flat_map(RegisterCluster::expand)
+                .collect();
+            Cluster::Single(info)
+        };
+        match self {
+            Cluster::Single(info) => vec![expand_children(info.clone())],
+            Cluster::Array(info, array_info) => array_info
+                .indexes()
+                .enumerate()
+                .map_while(|(i, index)| {
+                    let increment = array_info
+                        .dim_increment
+                        .checked_mul(u32::try_from(i).ok()?)?;
+                    let address_offset = info.address_offset.checked_add(increment)?;
+                    let mut info = info.clone();
+                    info.name = expand_name(&info.name, &index);
+                    info.address_offset = address_offset;
+                    Some(expand_children(info))
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Parse for Cluster {
     type Object = Self;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        assert_eq!(tree.name, "cluster");
+        if tree.name != "cluster" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "cluster".to_string()).into());
+        }
 
-        let info = ClusterInfo::parse(tree)?;
+        Self::_parse(tree, ClusterInfo::parse(tree)?)
+    }
+}
+
+impl Cluster {
+    /// Parses a cluster whose registers and clusters have already been parsed
+    pub(crate) fn parse_with(tree: &Element, children: Vec<RegisterCluster>) -> Result<Self> {
+        if tree.name != "cluster" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "cluster".to_string()).into());
+        }
+        Self::_parse(tree, ClusterInfo::parse_with(tree, children)?)
+    }
 
+    fn _parse(tree: &Element, info: ClusterInfo) -> Result<Self> {
         if tree.get_child("dimIncrement").is_some() {
             let array_info = DimElement::parse(tree)?;
             check_has_placeholder(&info.name, "cluster")?;
 
             if let Some(indices) = &array_info.dim_index {
                 if array_info.dim as usize != indices.len() {
-                    // TODO: replace with real error
-                    anyhow::bail!("Cluster index length mismatch");
+                    return Err(SVDError::DimIndexLength(
+                        tree.into(),
+                        array_info.dim,
+                        indices.len(),
+                    )
+                    .into());
                 }
             }
 
@@ -1,16 +1,19 @@
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
+use crate::parse;
 use crate::types::Parse;
 
 use crate::encode::{Encode, EncodeChildren};
 use crate::new_element;
 
 use crate::error::*;
+use crate::path::{ClusterPathIter, FieldPathIter, RegPathIter};
 use crate::svd::{
     register::{RegIter, RegIterMut},
     registercluster::RegisterCluster,
     registerproperties::RegisterProperties,
+    vendorextensions::VendorExtensions,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -35,6 +38,11 @@ pub struct ClusterInfo {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub description: Option<String>,
 
+    /// Specify the name of the original cluster if this cluster provides an alternative description
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub alternate_cluster: Option<String>,
+
     /// Specify the struct type name created in the device header file
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -43,6 +51,11 @@ pub struct ClusterInfo {
     pub default_register_properties: RegisterProperties,
 
     pub children: Vec<RegisterCluster>,
+
+    /// Vendor specific extensions of the cluster, kept as raw XML
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_extensions: Option<VendorExtensions>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -51,9 +64,11 @@ pub struct ClusterInfoBuilder {
     address_offset: Option<u32>,
     derived_from: Option<String>,
     description: Option<String>,
+    alternate_cluster: Option<String>,
     header_struct_name: Option<String>,
     default_register_properties: RegisterProperties,
     children: Option<Vec<RegisterCluster>>,
+    vendor_extensions: Option<VendorExtensions>,
 }
 
 impl From<ClusterInfo> for ClusterInfoBuilder {
@@ -63,9 +78,11 @@ impl From<ClusterInfo> for ClusterInfoBuilder {
             address_offset: Some(c.address_offset),
             derived_from: c.derived_from,
             description: c.description,
+            alternate_cluster: c.alternate_cluster,
             header_struct_name: c.header_struct_name,
             default_register_properties: c.default_register_properties,
             children: Some(c.children),
+            vendor_extensions: c.vendor_extensions,
         }
     }
 }
@@ -87,6 +104,10 @@ impl ClusterInfoBuilder {
         self.description = value;
         self
     }
+    pub fn alternate_cluster(mut self, value: Option<String>) -> Self {
+        self.alternate_cluster = value;
+        self
+    }
     pub fn header_struct_name(mut self, value: Option<String>) -> Self {
         self.header_struct_name = value;
         self
@@ -99,7 +120,11 @@ impl ClusterInfoBuilder {
         self.children = Some(value);
         self
     }
-    pub fn build(self) -> Result<ClusterInfo> {
+    pub fn vendor_extensions(mut self, value: Option<VendorExtensions>) -> Self {
+        self.vendor_extensions = value;
+        self
+    }
+    pub fn build(self) -> Result<ClusterInfo, Error> {
         (ClusterInfo {
             name: self
                 .name
@@ -109,13 +134,16 @@ impl ClusterInfoBuilder {
                 .ok_or_else(|| BuildError::Uninitialized("address_offset".to_string()))?,
             derived_from: self.derived_from,
             description: self.description,
+            alternate_cluster: self.alternate_cluster,
             header_struct_name: self.header_struct_name,
             default_register_properties: self.default_register_properties,
             children: self
                 .children
                 .ok_or_else(|| BuildError::Uninitialized("children".to_string()))?,
+            vendor_extensions: self.vendor_extensions,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -124,6 +152,12 @@ impl ClusterInfo {
     fn validate(self) -> Result<Self> {
         #[cfg(feature = "strict")]
         check_dimable_name(&self.name, "name")?;
+        #[cfg(feature = "strict")]
+        {
+            if let Some(name) = self.alternate_cluster.as_ref() {
+                check_dimable_name(name, "alternateCluster")?;
+            }
+        }
         if let Some(_name) = self.derived_from.as_ref() {
             #[cfg(feature = "strict")]
             check_derived_name(_name, "derivedFrom")?;
@@ -131,6 +165,11 @@ impl ClusterInfo {
             #[cfg(feature = "strict")]
             return Err(SVDError::EmptyCluster)?;
         }
+        #[cfg(feature = "strict")]
+        crate::svd::registercluster::check_alternates(
+            &self.children,
+            self.default_register_properties.size,
+        )?;
         Ok(self)
     }
 }
@@ -141,33 +180,49 @@ impl Parse for ClusterInfo {
 
     fn parse(tree: &Element) -> Result<Self> {
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In cluster `{}`", name))
+        Self::_parse(tree, name.clone(), || Self::parse_children(tree)).in_element("cluster", &name)
     }
 }
 
 impl ClusterInfo {
-    fn _parse(tree: &Element, name: String) -> Result<Self> {
+    /// Parses a cluster whose registers and clusters have already been parsed
+    pub(crate) fn parse_with(tree: &Element, children: Vec<RegisterCluster>) -> Result<Self> {
+        let name = tree.get_child_text("name")?;
+        Self::_parse(tree, name.clone(), || Ok(children)).in_element("cluster", &name)
+    }
+
+    fn parse_children(tree: &Element) -> Result<Vec<RegisterCluster>> {
+        tree.children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .filter(|t| t.name == "register" || t.name == "cluster")
+            .map(RegisterCluster::parse)
+            .collect()
+    }
+
+    fn _parse<F>(tree: &Element, name: String, children: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<Vec<RegisterCluster>>,
+    {
         ClusterInfoBuilder::default()
             .name(name)
             .derived_from(tree.attributes.get("derivedFrom").map(|s| s.to_owned()))
             .description(tree.get_child_text_opt("description")?)
+            .alternate_cluster(tree.get_child_text_opt("alternateCluster")?)
             .header_struct_name(tree.get_child_text_opt("headerStructName")?)
             .address_offset(tree.get_child_u32("addressOffset")?)
             .default_register_properties(RegisterProperties::parse(tree)?)
-            .children({
-                let children: Result<Vec<_>, _> = tree
-                    .children
-                    .iter()
-                    .filter(|t| t.name == "register" || t.name == "cluster")
-                    .map(RegisterCluster::parse)
-                    .collect();
-                children?
-            })
+            .children(children()?)
+            .vendor_extensions(parse::optional::<VendorExtensions>(
+                "vendorExtensions",
+                tree,
+            )?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 
     /// returns iterator over all registers cluster contains
-    pub fn reg_iter(&self) -> RegIter {
+    pub fn reg_iter(&self) -> RegIter<'_> {
         let mut rem: Vec<&RegisterCluster> = Vec::with_capacity(self.children.len());
         for r in self.children.iter().rev() {
             rem.push(r);
@@ -176,13 +231,31 @@ impl ClusterInfo {
     }
 
     /// returns mutable iterator over all registers cluster contains
-    pub fn reg_iter_mut(&mut self) -> RegIterMut {
+    pub fn reg_iter_mut(&mut self) -> RegIterMut<'_> {
         let mut rem: Vec<&mut RegisterCluster> = Vec::with_capacity(self.children.len());
         for r in self.children.iter_mut().rev() {
             rem.push(r);
         }
         RegIterMut { rem }
     }
+
+    /// returns iterator over all registers cluster contains
+    /// together with enclosing clusters, offsets relative to the cluster and inherited properties
+    pub fn reg_path_iter(&self) -> RegPathIter<'_> {
+        RegPathIter::new(None, &self.children, self.default_register_properties)
+    }
+
+    /// returns iterator over all clusters cluster contains
+    /// together with enclosing clusters, offsets relative to the cluster and inherited properties
+    pub fn cluster_path_iter(&self) -> ClusterPathIter<'_> {
+        ClusterPathIter::new(None, &self.children, self.default_register_properties)
+    }
+
+    /// returns iterator over all fields cluster contains
+    /// together with enclosing registers and clusters
+    pub fn field_path_iter(&self) -> FieldPathIter<'_> {
+        FieldPathIter::new(None, &self.children, self.default_register_properties)
+    }
 }
 
 impl Encode for ClusterInfo {
@@ -196,24 +269,48 @@ impl Encode for ClusterInfo {
                 .insert(String::from("derivedFrom"), v.to_string());
         }
 
-        e.children
-            .push(new_element("description", self.description.clone()));
+        e.children.push(XMLNode::Element(new_element(
+            "name",
+            Some(self.name.clone()),
+        )));
+
+        e.children.push(XMLNode::Element(new_element(
+            "description",
+            self.description.clone(),
+        )));
+
+        if let Some(v) = &self.alternate_cluster {
+            e.children.push(XMLNode::Element(new_element(
+                "alternateCluster",
+                Some(v.clone()),
+            )));
+        }
 
         if let Some(v) = &self.header_struct_name {
-            e.children
-                .push(new_element("headerStructName", Some(v.clone())));
+            e.children.push(XMLNode::Element(new_element(
+                "headerStructName",
+                Some(v.clone()),
+            )));
         }
 
-        e.children.push(new_element(
+        e.children.push(XMLNode::Element(new_element(
             "addressOffset",
             Some(format!("{}", self.address_offset)),
-        ));
+        )));
 
-        e.children
-            .extend(self.default_register_properties.encode()?);
+        e.children.extend(
+            self.default_register_properties
+                .encode()?
+                .into_iter()
+                .map(XMLNode::Element),
+        );
 
         for c in &self.children {
-            e.children.push(c.encode()?);
+            e.children.push(XMLNode::Element(c.encode()?));
+        }
+
+        if let Some(v) = &self.vendor_extensions {
+            e.children.push(XMLNode::Element(v.encode()?));
         }
 
         Ok(e)
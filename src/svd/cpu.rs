@@ -1,14 +1,15 @@
 use std::collections::HashMap;
 
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::elementext::ElementExt;
 use crate::encode::Encode;
 use crate::error::*;
 
 use crate::new_element;
-use crate::svd::endian::Endian;
-use crate::types::Parse;
+use crate::parse;
+use crate::svd::{endian::Endian, sauregions::SauRegionsConfig};
+use crate::types::{BoolParse, Parse};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -28,11 +29,63 @@ pub struct Cpu {
     /// Indicate whether the processor is equipped with a hardware floating point unit (FPU)
     pub fpu_present: bool,
 
+    /// Indicate whether the processor is equipped with a double precision floating point unit.
+    /// This element is valid only when `fpu_present` is set to `true`
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub fpu_double_precision: Option<bool>,
+
+    /// Indicates whether the processor implements the optional SIMD DSP extensions (DSP)
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dsp_present: Option<bool>,
+
+    /// Indicate whether the processor has an instruction cache
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub icache_present: Option<bool>,
+
+    /// Indicate whether the processor has a data cache
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dcache_present: Option<bool>,
+
+    /// Indicate whether the processor has an instruction tightly coupled memory
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub itcm_present: Option<bool>,
+
+    /// Indicate whether the processor has a data tightly coupled memory
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dtcm_present: Option<bool>,
+
+    /// Indicate whether the Vector Table Offset Register (VTOR) is implemented.
+    /// If not specified, then VTOR is assumed to be present
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vtor_present: Option<bool>,
+
     /// Define the number of bits available in the Nested Vectored Interrupt Controller (NVIC) for configuring priority
     pub nvic_priority_bits: u32,
 
     /// Indicate whether the processor implements a vendor-specific System Tick Timer
     pub has_vendor_systick: bool,
+
+    /// Add 1 to the highest interrupt number and specify this number in here
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub device_num_interrupts: Option<u32>,
+
+    /// Indicate the amount of regions in the Security Attribution Unit (SAU)
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub sau_num_regions: Option<u32>,
+
+    /// Set the configuration for the Security Attribution Unit (SAU) regions
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub sau_regions_config: Option<SauRegionsConfig>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -42,8 +95,18 @@ pub struct CpuBuilder {
     endian: Option<Endian>,
     mpu_present: Option<bool>,
     fpu_present: Option<bool>,
+    fpu_double_precision: Option<bool>,
+    dsp_present: Option<bool>,
+    icache_present: Option<bool>,
+    dcache_present: Option<bool>,
+    itcm_present: Option<bool>,
+    dtcm_present: Option<bool>,
+    vtor_present: Option<bool>,
     nvic_priority_bits: Option<u32>,
     has_vendor_systick: Option<bool>,
+    device_num_interrupts: Option<u32>,
+    sau_num_regions: Option<u32>,
+    sau_regions_config: Option<SauRegionsConfig>,
 }
 
 impl From<Cpu> for CpuBuilder {
@@ -54,8 +117,18 @@ impl From<Cpu> for CpuBuilder {
             endian: Some(c.endian),
             mpu_present: Some(c.mpu_present),
             fpu_present: Some(c.fpu_present),
+            fpu_double_precision: c.fpu_double_precision,
+            dsp_present: c.dsp_present,
+            icache_present: c.icache_present,
+            dcache_present: c.dcache_present,
+            itcm_present: c.itcm_present,
+            dtcm_present: c.dtcm_present,
+            vtor_present: c.vtor_present,
             nvic_priority_bits: Some(c.nvic_priority_bits),
             has_vendor_systick: Some(c.has_vendor_systick),
+            device_num_interrupts: c.device_num_interrupts,
+            sau_num_regions: c.sau_num_regions,
+            sau_regions_config: c.sau_regions_config,
         }
     }
 }
@@ -81,6 +154,34 @@ impl CpuBuilder {
         self.fpu_present = Some(value);
         self
     }
+    pub fn fpu_double_precision(mut self, value: Option<bool>) -> Self {
+        self.fpu_double_precision = value;
+        self
+    }
+    pub fn dsp_present(mut self, value: Option<bool>) -> Self {
+        self.dsp_present = value;
+        self
+    }
+    pub fn icache_present(mut self, value: Option<bool>) -> Self {
+        self.icache_present = value;
+        self
+    }
+    pub fn dcache_present(mut self, value: Option<bool>) -> Self {
+        self.dcache_present = value;
+        self
+    }
+    pub fn itcm_present(mut self, value: Option<bool>) -> Self {
+        self.itcm_present = value;
+        self
+    }
+    pub fn dtcm_present(mut self, value: Option<bool>) -> Self {
+        self.dtcm_present = value;
+        self
+    }
+    pub fn vtor_present(mut self, value: Option<bool>) -> Self {
+        self.vtor_present = value;
+        self
+    }
     pub fn nvic_priority_bits(mut self, value: u32) -> Self {
         self.nvic_priority_bits = Some(value);
         self
@@ -89,7 +190,19 @@ impl CpuBuilder {
         self.has_vendor_systick = Some(value);
         self
     }
-    pub fn build(self) -> Result<Cpu> {
+    pub fn device_num_interrupts(mut self, value: Option<u32>) -> Self {
+        self.device_num_interrupts = value;
+        self
+    }
+    pub fn sau_num_regions(mut self, value: Option<u32>) -> Self {
+        self.sau_num_regions = value;
+        self
+    }
+    pub fn sau_regions_config(mut self, value: Option<SauRegionsConfig>) -> Self {
+        self.sau_regions_config = value;
+        self
+    }
+    pub fn build(self) -> Result<Cpu, Error> {
         (Cpu {
             name: self
                 .name
@@ -106,21 +219,35 @@ impl CpuBuilder {
             fpu_present: self
                 .fpu_present
                 .ok_or_else(|| BuildError::Uninitialized("fpu_present".to_string()))?,
+            fpu_double_precision: self.fpu_double_precision,
+            dsp_present: self.dsp_present,
+            icache_present: self.icache_present,
+            dcache_present: self.dcache_present,
+            itcm_present: self.itcm_present,
+            dtcm_present: self.dtcm_present,
+            vtor_present: self.vtor_present,
             nvic_priority_bits: self
                 .nvic_priority_bits
                 .ok_or_else(|| BuildError::Uninitialized("nvic_priority_bits".to_string()))?,
             has_vendor_systick: self
                 .has_vendor_systick
                 .ok_or_else(|| BuildError::Uninitialized("has_vendor_systick".to_string()))?,
+            device_num_interrupts: self.device_num_interrupts,
+            sau_num_regions: self.sau_num_regions,
+            sau_regions_config: self.sau_regions_config,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
 impl Cpu {
-    #[allow(clippy::unnecessary_wraps)]
     fn validate(self) -> Result<Self> {
-        // TODO
+        #[cfg(feature = "strict")]
+        if let (Some(config), Some(num_regions)) = (&self.sau_regions_config, self.sau_num_regions)
+        {
+            config.check_num_regions(num_regions)?;
+        }
         Ok(self)
     }
 }
@@ -131,7 +258,7 @@ impl Parse for Cpu {
 
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "cpu" {
-            return Err(SVDError::NameMismatch(tree.clone()).into());
+            return Err(SVDError::NameMismatch(tree.into()).into());
         }
 
         CpuBuilder::default()
@@ -140,9 +267,23 @@ impl Parse for Cpu {
             .endian(Endian::parse(tree.get_child_elem("endian")?)?)
             .mpu_present(tree.get_child_bool("mpuPresent")?)
             .fpu_present(tree.get_child_bool("fpuPresent")?)
+            .fpu_double_precision(parse::optional::<BoolParse>("fpuDP", tree)?)
+            .dsp_present(parse::optional::<BoolParse>("dspPresent", tree)?)
+            .icache_present(parse::optional::<BoolParse>("icachePresent", tree)?)
+            .dcache_present(parse::optional::<BoolParse>("dcachePresent", tree)?)
+            .itcm_present(parse::optional::<BoolParse>("itcmPresent", tree)?)
+            .dtcm_present(parse::optional::<BoolParse>("dtcmPresent", tree)?)
+            .vtor_present(parse::optional::<BoolParse>("vtorPresent", tree)?)
             .nvic_priority_bits(tree.get_child_u32("nvicPrioBits")?)
             .has_vendor_systick(tree.get_child_bool("vendorSystickConfig")?)
+            .device_num_interrupts(parse::optional::<u32>("deviceNumInterrupts", tree)?)
+            .sau_num_regions(parse::optional::<u32>("sauNumRegions", tree)?)
+            .sau_regions_config(parse::optional::<SauRegionsConfig>(
+                "sauRegionsConfig",
+                tree,
+            )?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -150,25 +291,59 @@ impl Encode for Cpu {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
+        let mut children = vec![
+            new_element("name", Some(self.name.clone())),
+            new_element("revision", Some(self.revision.clone())),
+            self.endian.encode()?,
+            new_element("mpuPresent", Some(format!("{}", self.mpu_present))),
+            new_element("fpuPresent", Some(format!("{}", self.fpu_present))),
+        ];
+        if let Some(v) = &self.fpu_double_precision {
+            children.push(new_element("fpuDP", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.dsp_present {
+            children.push(new_element("dspPresent", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.icache_present {
+            children.push(new_element("icachePresent", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.dcache_present {
+            children.push(new_element("dcachePresent", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.itcm_present {
+            children.push(new_element("itcmPresent", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.dtcm_present {
+            children.push(new_element("dtcmPresent", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.vtor_present {
+            children.push(new_element("vtorPresent", Some(format!("{}", v))));
+        }
+        children.push(new_element(
+            "nvicPrioBits",
+            Some(format!("{}", self.nvic_priority_bits)),
+        ));
+        children.push(new_element(
+            "vendorSystickConfig",
+            Some(format!("{}", self.has_vendor_systick)),
+        ));
+        if let Some(v) = &self.device_num_interrupts {
+            children.push(new_element("deviceNumInterrupts", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.sau_num_regions {
+            children.push(new_element("sauNumRegions", Some(format!("{}", v))));
+        }
+        if let Some(v) = &self.sau_regions_config {
+            children.push(v.encode()?);
+        }
+
         Ok(Element {
             prefix: None,
             namespace: None,
             namespaces: None,
             name: String::from("cpu"),
             attributes: HashMap::new(),
-            children: vec![
-                new_element("name", Some(self.name.clone())),
-                new_element("revision", Some(self.revision.clone())),
-                self.endian.encode()?,
-                new_element("mpuPresent", Some(format!("{}", self.mpu_present))),
-                new_element("fpuPresent", Some(format!("{}", self.fpu_present))),
-                new_element("nvicPrioBits", Some(format!("{}", self.nvic_priority_bits))),
-                new_element(
-                    "vendorSystickConfig",
-                    Some(format!("{}", self.has_vendor_systick)),
-                ),
-            ],
-            text: None,
+            children: children.into_iter().map(XMLNode::Element).collect(),
         })
     }
 }
@@ -183,21 +358,23 @@ impl Cpu {
 mod tests {
     use super::*;
     use crate::run_test;
+    use crate::svd::sauregions::{SauAccess, SauRegionBuilder, SauRegionsConfigBuilder};
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            CpuBuilder::default()
-                .name("EFM32JG12B500F512GM48".to_string())
-                .revision("5.1.1".to_string())
-                .endian(Endian::Little)
-                .mpu_present(true)
-                .fpu_present(true)
-                .nvic_priority_bits(8)
-                .has_vendor_systick(false)
-                .build()
-                .unwrap(),
-            "
+        let tests = [
+            (
+                CpuBuilder::default()
+                    .name("EFM32JG12B500F512GM48".to_string())
+                    .revision("5.1.1".to_string())
+                    .endian(Endian::Little)
+                    .mpu_present(true)
+                    .fpu_present(true)
+                    .nvic_priority_bits(8)
+                    .has_vendor_systick(false)
+                    .build()
+                    .unwrap(),
+                "
                     <cpu>
                         <name>EFM32JG12B500F512GM48</name>  
                         <revision>5.1.1</revision>
@@ -208,8 +385,80 @@ mod tests {
                         <vendorSystickConfig>false</vendorSystickConfig>
                     </cpu>
                 ",
-        )];
+            ),
+            (
+                CpuBuilder::default()
+                    .name("CM33".to_string())
+                    .revision("r0p0".to_string())
+                    .endian(Endian::Little)
+                    .mpu_present(true)
+                    .fpu_present(true)
+                    .fpu_double_precision(Some(false))
+                    .dsp_present(Some(true))
+                    .icache_present(Some(false))
+                    .dcache_present(Some(false))
+                    .itcm_present(Some(false))
+                    .dtcm_present(Some(false))
+                    .vtor_present(Some(true))
+                    .nvic_priority_bits(3)
+                    .has_vendor_systick(false)
+                    .device_num_interrupts(Some(65))
+                    .sau_num_regions(Some(8))
+                    .build()
+                    .unwrap(),
+                "
+                    <cpu>
+                        <name>CM33</name>
+                        <revision>r0p0</revision>
+                        <endian>little</endian>
+                        <mpuPresent>true</mpuPresent>
+                        <fpuPresent>true</fpuPresent>
+                        <fpuDP>false</fpuDP>
+                        <dspPresent>true</dspPresent>
+                        <icachePresent>false</icachePresent>
+                        <dcachePresent>false</dcachePresent>
+                        <itcmPresent>false</itcmPresent>
+                        <dtcmPresent>false</dtcmPresent>
+                        <vtorPresent>true</vtorPresent>
+                        <nvicPrioBits>3</nvicPrioBits>
+                        <vendorSystickConfig>false</vendorSystickConfig>
+                        <deviceNumInterrupts>65</deviceNumInterrupts>
+                        <sauNumRegions>8</sauNumRegions>
+                    </cpu>
+                ",
+            ),
+        ];
 
         run_test::<Cpu>(&tests[..]);
     }
+
+    #[test]
+    fn too_many_sau_regions() {
+        let region = SauRegionBuilder::default()
+            .base(0x10000000)
+            .limit(0x1000001F)
+            .access(SauAccess::NonSecure)
+            .build()
+            .unwrap();
+        let config = SauRegionsConfigBuilder::default()
+            .regions(vec![region.clone(), region])
+            .build()
+            .unwrap();
+
+        let cpu = CpuBuilder::default()
+            .name("CM33".to_string())
+            .revision("r0p0".to_string())
+            .endian(Endian::Little)
+            .mpu_present(true)
+            .fpu_present(true)
+            .nvic_priority_bits(3)
+            .has_vendor_systick(false)
+            .sau_num_regions(Some(1))
+            .sau_regions_config(Some(config))
+            .build();
+        #[cfg(feature = "strict")]
+        cpu.expect_err("more regions than sauNumRegions");
+        #[cfg(not(feature = "strict"))]
+        cpu.expect("regions are only counted with `strict`");
+    }
 }
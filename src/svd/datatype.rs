@@ -0,0 +1,121 @@
+use crate::elementext::ElementExt;
+
+use xmltree::Element;
+
+use crate::types::Parse;
+
+use crate::encode::Encode;
+use crate::error::*;
+use crate::new_element;
+
+/// C data type used for a register in the device header file
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataType {
+    /// `uint8_t`
+    U8,
+    /// `uint16_t`
+    U16,
+    /// `uint32_t`
+    U32,
+    /// `uint64_t`
+    U64,
+    /// `int8_t`
+    I8,
+    /// `int16_t`
+    I16,
+    /// `int32_t`
+    I32,
+    /// `int64_t`
+    I64,
+    /// `uint8_t *`
+    U8Ptr,
+    /// `uint16_t *`
+    U16Ptr,
+    /// `uint32_t *`
+    U32Ptr,
+    /// `uint64_t *`
+    U64Ptr,
+    /// `int8_t *`
+    I8Ptr,
+    /// `int16_t *`
+    I16Ptr,
+    /// `int32_t *`
+    I32Ptr,
+    /// `int64_t *`
+    I64Ptr,
+}
+
+impl Parse for DataType {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        use self::DataType::*;
+        let text = tree.get_content()?;
+
+        Ok(match text.as_ref() {
+            "uint8_t" => U8,
+            "uint16_t" => U16,
+            "uint32_t" => U32,
+            "uint64_t" => U64,
+            "int8_t" => I8,
+            "int16_t" => I16,
+            "int32_t" => I32,
+            "int64_t" => I64,
+            "uint8_t *" => U8Ptr,
+            "uint16_t *" => U16Ptr,
+            "uint32_t *" => U32Ptr,
+            "uint64_t *" => U64Ptr,
+            "int8_t *" => I8Ptr,
+            "int16_t *" => I16Ptr,
+            "int32_t *" => I32Ptr,
+            "int64_t *" => I64Ptr,
+            s => return Err(SVDError::InvalidDataType(tree.into(), s.into()).into()),
+        })
+    }
+}
+
+impl Encode for DataType {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        use self::DataType::*;
+        let v = match *self {
+            U8 => "uint8_t",
+            U16 => "uint16_t",
+            U32 => "uint32_t",
+            U64 => "uint64_t",
+            I8 => "int8_t",
+            I16 => "int16_t",
+            I32 => "int32_t",
+            I64 => "int64_t",
+            U8Ptr => "uint8_t *",
+            U16Ptr => "uint16_t *",
+            U32Ptr => "uint32_t *",
+            U64Ptr => "uint64_t *",
+            I8Ptr => "int8_t *",
+            I16Ptr => "int16_t *",
+            I32Ptr => "int32_t *",
+            I64Ptr => "int64_t *",
+        };
+
+        Ok(new_element("dataType", Some(v.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (DataType::U32, "<dataType>uint32_t</dataType>"),
+            (DataType::I8Ptr, "<dataType>int8_t *</dataType>"),
+        ];
+
+        run_test::<DataType>(&tests[..]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::elementext::ElementExt;
 use std::collections::HashMap;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use rayon::prelude::*;
 
@@ -10,16 +10,42 @@ use crate::types::Parse;
 use crate::encode::{Encode, EncodeChildren};
 use crate::error::*;
 
+use crate::address::{self, AddressMap, RegisterAddress};
+use crate::derive_from::DeriveFrom;
 use crate::new_element;
-use crate::svd::{cpu::Cpu, peripheral::Peripheral, registerproperties::RegisterProperties};
+use crate::path::{self, ClusterPath, FieldPath, RegisterPath};
+use crate::svd::{
+    clusterinfo::ClusterInfo,
+    cpu::Cpu,
+    peripheral::Peripheral,
+    registercluster::RegisterCluster,
+    registerinfo::RegisterInfo,
+    registerproperties::RegisterProperties,
+    vendorextensions::{VendorExtension, VendorExtensions},
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
 #[non_exhaustive]
 pub struct Device {
+    /// Specify the vendor of the device using the full name
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor: Option<String>,
+
+    /// Specify the vendor abbreviation without spaces or special characters
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_id: Option<String>,
+
     /// The string identifies the device or device series. Device names are required to be unique
     pub name: String,
 
+    /// Specify the name of the device series
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub series: Option<String>,
+
     /// Specify the compliant CMSIS-SVD schema version
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -35,6 +61,21 @@ pub struct Device {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub description: Option<String>,
 
+    /// The text will be copied into the header section of the generated device header file and shall contain the legal disclaimer
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub license_text: Option<String>,
+
+    /// Specify the file name (without extension) of the device-specific system include file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub header_system_filename: Option<String>,
+
+    /// This string is prepended to all type definition names generated in the CMSIS-Core device header file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub header_definitions_prefix: Option<String>,
+
     /// Define the number of data bits uniquely selected by each address
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -54,42 +95,73 @@ pub struct Device {
     pub peripherals: Vec<Peripheral>,
 
     pub default_register_properties: RegisterProperties,
+
+    /// Vendor specific extensions of the device, kept as raw XML
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_extensions: Option<VendorExtensions>,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct DeviceBuilder {
+    vendor: Option<String>,
+    vendor_id: Option<String>,
     name: Option<String>,
+    series: Option<String>,
     schema_version: Option<String>,
     version: Option<String>,
     description: Option<String>,
+    license_text: Option<String>,
+    header_system_filename: Option<String>,
+    header_definitions_prefix: Option<String>,
     address_unit_bits: Option<u32>,
     width: Option<u32>,
     cpu: Option<Cpu>,
     peripherals: Option<Vec<Peripheral>>,
     default_register_properties: RegisterProperties,
+    vendor_extensions: Option<VendorExtensions>,
 }
 
 impl From<Device> for DeviceBuilder {
     fn from(d: Device) -> Self {
         Self {
+            vendor: d.vendor,
+            vendor_id: d.vendor_id,
             name: Some(d.name),
+            series: d.series,
             schema_version: d.schema_version,
             version: d.version,
             description: d.description,
+            license_text: d.license_text,
+            header_system_filename: d.header_system_filename,
+            header_definitions_prefix: d.header_definitions_prefix,
             address_unit_bits: d.address_unit_bits,
             width: d.width,
             cpu: d.cpu,
             peripherals: Some(d.peripherals),
             default_register_properties: d.default_register_properties,
+            vendor_extensions: d.vendor_extensions,
         }
     }
 }
 
 impl DeviceBuilder {
+    pub fn vendor(mut self, value: Option<String>) -> Self {
+        self.vendor = value;
+        self
+    }
+    pub fn vendor_id(mut self, value: Option<String>) -> Self {
+        self.vendor_id = value;
+        self
+    }
     pub fn name(mut self, value: String) -> Self {
         self.name = Some(value);
         self
     }
+    pub fn series(mut self, value: Option<String>) -> Self {
+        self.series = value;
+        self
+    }
     pub fn schema_version(mut self, value: Option<String>) -> Self {
         self.schema_version = value;
         self
@@ -102,6 +174,18 @@ impl DeviceBuilder {
         self.description = value;
         self
     }
+    pub fn license_text(mut self, value: Option<String>) -> Self {
+        self.license_text = value;
+        self
+    }
+    pub fn header_system_filename(mut self, value: Option<String>) -> Self {
+        self.header_system_filename = value;
+        self
+    }
+    pub fn header_definitions_prefix(mut self, value: Option<String>) -> Self {
+        self.header_definitions_prefix = value;
+        self
+    }
     pub fn address_unit_bits(mut self, value: Option<u32>) -> Self {
         self.address_unit_bits = value;
         self
@@ -122,14 +206,24 @@ impl DeviceBuilder {
         self.default_register_properties = value;
         self
     }
-    pub fn build(self) -> Result<Device> {
+    pub fn vendor_extensions(mut self, value: Option<VendorExtensions>) -> Self {
+        self.vendor_extensions = value;
+        self
+    }
+    pub fn build(self) -> Result<Device, Error> {
         (Device {
+            vendor: self.vendor,
+            vendor_id: self.vendor_id,
             name: self
                 .name
                 .ok_or_else(|| BuildError::Uninitialized("name".to_string()))?,
+            series: self.series,
             schema_version: self.schema_version,
             version: self.version,
             description: self.description,
+            license_text: self.license_text,
+            header_system_filename: self.header_system_filename,
+            header_definitions_prefix: self.header_definitions_prefix,
             address_unit_bits: self.address_unit_bits,
             width: self.width,
             cpu: self.cpu,
@@ -137,8 +231,10 @@ impl DeviceBuilder {
                 .peripherals
                 .ok_or_else(|| BuildError::Uninitialized("peripherals".to_string()))?,
             default_register_properties: self.default_register_properties,
+            vendor_extensions: self.vendor_extensions,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -148,8 +244,235 @@ impl Device {
         if self.peripherals.is_empty() {
             return Err(SVDError::EmptyDevice.into());
         }
+        #[cfg(feature = "strict")]
+        self.check_alternate_peripherals()?;
+        #[cfg(feature = "strict")]
+        self.check_address_blocks()?;
         Ok(self)
     }
+
+    /// Checks that the registers of peripherals without a default register size
+    /// are inside their address blocks, taking the register size from the device.
+    /// Other peripherals are checked when they are built
+    #[cfg(feature = "strict")]
+    fn check_address_blocks(&self) -> Result<()> {
+        if self.default_register_properties.size.is_none() {
+            return Ok(());
+        }
+        for p in &self.peripherals {
+            if p.default_register_properties.size.is_none() {
+                let defaults = p
+                    .default_register_properties
+                    .derive_from(&self.default_register_properties);
+                p.check_address_blocks(defaults)
+                    .in_element("peripheral", &p.name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every `alternatePeripheral` names a peripheral
+    /// which occupies the same addresses.
+    ///
+    /// Names are matched against the elements of peripheral arrays, so that `A0`
+    /// refers to the first element of `A%s`. Every element of an array of alternate
+    /// peripherals is checked, unless all elements name the same peripheral:
+    /// then the element address ranges only grow with the index, so the elements
+    /// overlapping the peripheral are consecutive and checking the first and the
+    /// last element is enough
+    #[cfg(feature = "strict")]
+    fn check_alternate_peripherals(&self) -> Result<()> {
+        use crate::svd::dimelement::{expand_name, match_name};
+        use crate::svd::peripheralinfo::PeripheralInfo;
+
+        // Address range of a peripheral at `base_address`,
+        // only its base address if no address block is known
+        fn span(p: &PeripheralInfo, base_address: u64) -> (u64, u64) {
+            let start = p.address_block.iter().map(|b| u64::from(b.offset)).min();
+            let end = p
+                .address_block
+                .iter()
+                .map(|b| u64::from(b.offset) + u64::from(b.size.max(1)))
+                .max();
+            match (start, end) {
+                (Some(start), Some(end)) => (
+                    base_address.saturating_add(start),
+                    base_address.saturating_add(end),
+                ),
+                _ => (base_address, base_address.saturating_add(1)),
+            }
+        }
+
+        // Peripheral or array element named `name` with its base address
+        let find = |name: &str| {
+            self.peripherals.iter().find_map(|o| match o {
+                Peripheral::Single(info) => {
+                    Some((info, info.base_address)).filter(|_| info.name == name)
+                }
+                Peripheral::Array(info, dim) => {
+                    let i = dim.position(match_name(&info.name, name)?)?;
+                    let offset = u64::from(dim.dim_increment).checked_mul(i as u64)?;
+                    Some((info, info.base_address.checked_add(offset)?))
+                }
+            })
+        };
+
+        for p in &self.peripherals {
+            let alternate = match &p.alternate_peripheral {
+                Some(alternate) => alternate,
+                None => continue,
+            };
+            let instances: Box<dyn Iterator<Item = _>> = match p {
+                Peripheral::Single(info) => Box::new(core::iter::once((
+                    info.name.clone(),
+                    alternate.clone(),
+                    info.base_address,
+                ))),
+                Peripheral::Array(info, dim) => {
+                    let last = dim.len().saturating_sub(1);
+                    let elements: Box<dyn Iterator<Item = usize>> = if alternate.contains("%s") {
+                        Box::new(0..dim.len())
+                    } else {
+                        Box::new(vec![0, last].into_iter())
+                    };
+                    Box::new(elements.filter_map(move |i| {
+                        let index = dim.index(i)?;
+                        let offset = u64::from(dim.dim_increment).checked_mul(i as u64)?;
+                        Some((
+                            expand_name(&info.name, &index),
+                            expand_name(alternate, &index),
+                            info.base_address.saturating_add(offset),
+                        ))
+                    }))
+                }
+            };
+            for (name, alternate, base_address) in instances {
+                let (original, original_base) = find(&alternate)
+                    .ok_or_else(|| AlternateError::NotFound(name.clone(), alternate.clone()))?;
+                let (start, end) = span(p, base_address);
+                let (o_start, o_end) = span(original, original_base);
+                if start >= o_end || o_start >= end {
+                    return Err(AlternateError::NotOverlapping(name, alternate).into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Device {
+    /// Returns the vendor extension `T` parsed by a parser registered
+    /// in the [`Config`](crate::parse::Config) used to parse the device
+    pub fn extension<T: VendorExtension>(&self) -> Option<&T> {
+        self.vendor_extensions.as_ref()?.get::<T>()
+    }
+
+    /// Returns a copy of the device with all `derivedFrom` references of
+    /// peripherals, clusters, registers, fields and enumerated values resolved
+    pub fn resolve_derivations(&self) -> Result<Self> {
+        crate::derive_from::resolve_derivations(self)
+    }
+
+    /// Returns the properties of `register` inherited from the enclosing
+    /// `clusters` (outermost first), `peripheral` and the device.
+    /// Properties specified on an inner element override outer ones
+    pub fn effective_properties(
+        &self,
+        peripheral: &Peripheral,
+        clusters: &[&ClusterInfo],
+        register: &RegisterInfo,
+    ) -> RegisterProperties {
+        let mut props = peripheral
+            .default_register_properties
+            .derive_from(&self.default_register_properties);
+        for c in clusters {
+            props = c.default_register_properties.derive_from(&props);
+        }
+        register.properties().derive_from(&props)
+    }
+
+    /// Returns a copy of the device where every register has its size, access
+    /// and reset values filled in with the values inherited from its parents
+    pub fn inherit_register_properties(&self) -> Self {
+        fn inherit(children: &mut [RegisterCluster], parent: &RegisterProperties) {
+            for rc in children {
+                match rc {
+                    RegisterCluster::Register(r) => {
+                        let props = r.properties().derive_from(parent);
+                        r.set_properties(&props);
+                    }
+                    RegisterCluster::Cluster(c) => {
+                        let props = c.default_register_properties.derive_from(parent);
+                        inherit(&mut c.children, &props);
+                    }
+                }
+            }
+        }
+
+        let mut device = self.clone();
+        for p in &mut device.peripherals {
+            let props = p
+                .default_register_properties
+                .derive_from(&self.default_register_properties);
+            if let Some(registers) = p.registers.as_mut() {
+                inherit(registers, &props);
+            }
+        }
+        device
+    }
+
+    /// Returns every register of the device with its absolute address,
+    /// see [`address::register_addresses`]
+    pub fn register_addresses(&self) -> Vec<RegisterAddress<'_>> {
+        address::register_addresses(self)
+    }
+
+    /// Returns registers which contain `address`,
+    /// see [`address::find_by_address`]
+    pub fn find_by_address(&self, address: u64) -> Vec<RegisterAddress<'_>> {
+        address::find_by_address(self, address)
+    }
+
+    /// Returns the registers of the device sorted by address,
+    /// see [`AddressMap`]
+    pub fn address_map(&self) -> AddressMap<'_> {
+        AddressMap::new(self)
+    }
+
+    /// Returns iterator over all registers of all peripherals
+    /// together with enclosing elements, offsets and inherited properties
+    pub fn reg_path_iter(&self) -> impl Iterator<Item = RegisterPath<'_>> {
+        path::device_reg_path_iter(self)
+    }
+
+    /// Returns iterator over all clusters of all peripherals
+    /// together with enclosing elements, offsets and inherited properties
+    pub fn cluster_path_iter(&self) -> impl Iterator<Item = ClusterPath<'_>> {
+        path::device_cluster_path_iter(self)
+    }
+
+    /// Returns iterator over all fields of all peripherals
+    /// together with enclosing registers, clusters and peripheral
+    pub fn field_path_iter(&self) -> impl Iterator<Item = FieldPath<'_>> {
+        path::device_field_path_iter(self)
+    }
+
+    /// Returns a copy of the device with every peripheral, register, cluster
+    /// and field array replaced by its elements
+    pub fn expand_arrays(&self) -> Self {
+        let mut device = self.clone();
+        device.peripherals = device
+            .peripherals
+            .iter()
+            .flat_map(Peripheral::expand)
+            .collect();
+        for p in &mut device.peripherals {
+            if let Some(registers) = p.registers.as_mut() {
+                *registers = registers.iter().flat_map(RegisterCluster::expand).collect();
+            }
+        }
+        device
+    }
 }
 
 impl Parse for Device {
@@ -158,35 +481,63 @@ impl Parse for Device {
 
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "device" {
-            return Err(SVDError::NotExpectedTag(tree.clone(), "device".to_string()).into());
+            return Err(SVDError::NotExpectedTag(tree.into(), "device".to_string()).into());
         }
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In device `{}`", name))
+        Self::_parse(tree, name.clone(), || Self::parse_peripherals(tree))
+            .in_element("device", &name)
     }
 }
 
 impl Device {
+    /// Parses a device whose peripherals have already been parsed
+    pub(crate) fn parse_with(tree: &Element, peripherals: Vec<Peripheral>) -> Result<Self> {
+        if tree.name != "device" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "device".to_string()).into());
+        }
+        let name = tree.get_child_text("name")?;
+        Self::_parse(tree, name.clone(), || {
+            tree.get_child_elem("peripherals").map(|_| peripherals)
+        })
+        .in_element("device", &name)
+    }
+
+    fn parse_peripherals(tree: &Element) -> Result<Vec<Peripheral>> {
+        tree.get_child_elem("peripherals")?
+            .children
+            .par_iter()
+            .filter_map(XMLNode::as_element)
+            .map(Peripheral::parse)
+            .collect()
+    }
+
     /// Parses a SVD file
-    fn _parse(tree: &Element, name: String) -> Result<Self> {
+    fn _parse<F>(tree: &Element, name: String, peripherals: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<Vec<Peripheral>>,
+    {
         DeviceBuilder::default()
+            .vendor(tree.get_child_text_opt("vendor")?)
+            .vendor_id(tree.get_child_text_opt("vendorID")?)
             .name(name)
+            .series(tree.get_child_text_opt("series")?)
             .schema_version(tree.attributes.get("schemaVersion").cloned())
             .cpu(parse::optional::<Cpu>("cpu", tree)?)
             .version(tree.get_child_text_opt("version")?)
             .description(tree.get_child_text_opt("description")?)
+            .license_text(tree.get_child_text_opt("licenseText")?)
+            .header_system_filename(tree.get_child_text_opt("headerSystemFilename")?)
+            .header_definitions_prefix(tree.get_child_text_opt("headerDefinitionsPrefix")?)
             .address_unit_bits(parse::optional::<u32>("addressUnitBits", tree)?)
-            .width(None)
-            .peripherals({
-                let ps: Result<Vec<_>, _> = tree
-                    .get_child_elem("peripherals")?
-                    .children
-                    .par_iter()
-                    .map(Peripheral::parse)
-                    .collect();
-                ps?
-            })
+            .width(parse::optional::<u32>("width", tree)?)
+            .peripherals(peripherals()?)
             .default_register_properties(RegisterProperties::parse(tree)?)
+            .vendor_extensions(parse::optional::<VendorExtensions>(
+                "vendorExtensions",
+                tree,
+            )?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -200,8 +551,7 @@ impl Encode for Device {
             namespaces: None,
             name: String::from("device"),
             attributes: HashMap::new(),
-            children: vec![new_element("name", Some(self.name.clone()))],
-            text: None,
+            children: Vec::new(),
         };
 
         elem.attributes.insert(
@@ -219,46 +569,449 @@ impl Encode for Device {
             );
         }
 
-        if let Some(v) = &self.version {
-            elem.children.push(new_element("version", Some(v.clone())));
+        if let Some(v) = &self.vendor {
+            elem.children
+                .push(XMLNode::Element(new_element("vendor", Some(v.clone()))));
         }
 
-        if let Some(v) = &self.description {
+        if let Some(v) = &self.vendor_id {
             elem.children
-                .push(new_element("description", Some(v.clone())));
+                .push(XMLNode::Element(new_element("vendorID", Some(v.clone()))));
         }
 
-        if let Some(v) = &self.address_unit_bits {
+        elem.children.push(XMLNode::Element(new_element(
+            "name",
+            Some(self.name.clone()),
+        )));
+
+        if let Some(v) = &self.series {
             elem.children
-                .push(new_element("addressUnitBits", Some(format!("{}", v))));
+                .push(XMLNode::Element(new_element("series", Some(v.clone()))));
         }
 
-        if let Some(v) = &self.width {
+        if let Some(v) = &self.version {
             elem.children
-                .push(new_element("width", Some(format!("{}", v))));
+                .push(XMLNode::Element(new_element("version", Some(v.clone()))));
         }
 
-        elem.children
-            .extend(self.default_register_properties.encode()?);
+        if let Some(v) = &self.description {
+            elem.children.push(XMLNode::Element(new_element(
+                "description",
+                Some(v.clone()),
+            )));
+        }
+
+        if let Some(v) = &self.license_text {
+            elem.children.push(XMLNode::Element(new_element(
+                "licenseText",
+                Some(v.clone()),
+            )));
+        }
 
         if let Some(v) = &self.cpu {
-            elem.children.push(v.encode()?);
+            elem.children.push(XMLNode::Element(v.encode()?));
+        }
+
+        if let Some(v) = &self.header_system_filename {
+            elem.children.push(XMLNode::Element(new_element(
+                "headerSystemFilename",
+                Some(v.clone()),
+            )));
+        }
+
+        if let Some(v) = &self.header_definitions_prefix {
+            elem.children.push(XMLNode::Element(new_element(
+                "headerDefinitionsPrefix",
+                Some(v.clone()),
+            )));
+        }
+
+        if let Some(v) = &self.address_unit_bits {
+            elem.children.push(XMLNode::Element(new_element(
+                "addressUnitBits",
+                Some(format!("{}", v)),
+            )));
+        }
+
+        if let Some(v) = &self.width {
+            elem.children.push(XMLNode::Element(new_element(
+                "width",
+                Some(format!("{}", v)),
+            )));
         }
 
+        elem.children.extend(
+            self.default_register_properties
+                .encode()?
+                .into_iter()
+                .map(XMLNode::Element),
+        );
+
         let peripherals: Result<Vec<_>, _> =
             self.peripherals.iter().map(Peripheral::encode).collect();
-        elem.children.push(Element {
+        elem.children.push(XMLNode::Element(Element {
             prefix: None,
             namespace: None,
             namespaces: None,
             name: String::from("peripherals"),
             attributes: HashMap::new(),
-            children: peripherals?,
-            text: None,
-        });
+            children: peripherals?.into_iter().map(XMLNode::Element).collect(),
+        }));
+
+        if let Some(v) = &self.vendor_extensions {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        }
 
         Ok(elem)
     }
 }
 
-// TODO: test device encoding and decoding
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::peripheralinfo::PeripheralInfoBuilder;
+    use crate::svd::{access::Access, protection::Protection};
+
+    #[test]
+    fn alternate_peripheral() {
+        let device = |alternate: &str, base_address: &str| {
+            crate::parse(&format!(
+                "
+                <device>
+                    <name>TEST</name>
+                    <peripherals>
+                        <peripheral>
+                            <name>USART1</name>
+                            <baseAddress>0x40013800</baseAddress>
+                            <addressBlock>
+                                <offset>0x0</offset>
+                                <size>0x400</size>
+                                <usage>registers</usage>
+                            </addressBlock>
+                        </peripheral>
+                        <peripheral>
+                            <name>UART1</name>
+                            <alternatePeripheral>{}</alternatePeripheral>
+                            <baseAddress>{}</baseAddress>
+                        </peripheral>
+                    </peripherals>
+                </device>
+                ",
+                alternate, base_address
+            ))
+        };
+
+        let d = device("USART1", "0x40013800").unwrap();
+        assert_eq!(
+            d.peripherals[1].alternate_peripheral,
+            Some("USART1".to_string())
+        );
+        #[cfg(feature = "strict")]
+        {
+            device("USART1", "0x40013c00").expect_err("addresses do not overlap");
+            device("USART2", "0x40013800").expect_err("alternate does not exist");
+        }
+        #[cfg(not(feature = "strict"))]
+        device("USART2", "0x40013800").expect("alternates are only checked with `strict`");
+    }
+
+    #[test]
+    fn alternate_array_element() {
+        let device = |base_address: &str| {
+            crate::parse(&format!(
+                "
+                <device>
+                    <name>TEST</name>
+                    <peripherals>
+                        <peripheral>
+                            <name>USART%s</name>
+                            <dim>2</dim>
+                            <dimIncrement>0x400</dimIncrement>
+                            <baseAddress>0x40013800</baseAddress>
+                            <addressBlock>
+                                <offset>0x0</offset>
+                                <size>0x400</size>
+                                <usage>registers</usage>
+                            </addressBlock>
+                        </peripheral>
+                        <peripheral>
+                            <name>UART1</name>
+                            <alternatePeripheral>USART1</alternatePeripheral>
+                            <baseAddress>{}</baseAddress>
+                        </peripheral>
+                    </peripherals>
+                </device>
+                ",
+                base_address
+            ))
+        };
+
+        device("0x40013c00").unwrap();
+        #[cfg(feature = "strict")]
+        device("0x40013800").expect_err("addresses do not overlap");
+    }
+
+    #[test]
+    fn alternate_array_middle_element() {
+        let device = |alternates: &str| {
+            crate::parse(&format!(
+                "
+                <device>
+                    <name>TEST</name>
+                    <peripherals>
+                        <peripheral>
+                            <name>USART%s</name>
+                            <dim>3</dim>
+                            <dimIncrement>0x400</dimIncrement>
+                            <alternatePeripheral>UART%s</alternatePeripheral>
+                            <baseAddress>0x40013800</baseAddress>
+                        </peripheral>
+                        {}
+                    </peripherals>
+                </device>
+                ",
+                alternates
+            ))
+        };
+        let uart = |i: u32, base_address: u32| {
+            format!(
+                "<peripheral><name>UART{}</name><baseAddress>0x{:x}</baseAddress></peripheral>",
+                i, base_address
+            )
+        };
+
+        let all = [0, 1, 2].iter().map(|&i| uart(i, 0x40013800 + 0x400 * i));
+        device(&all.collect::<String>()).unwrap();
+        let missing = uart(0, 0x40013800) + &uart(2, 0x40014000);
+        #[cfg(feature = "strict")]
+        device(&missing).expect_err("UART1 is missing");
+        #[cfg(not(feature = "strict"))]
+        device(&missing).expect("alternates are only checked with `strict`");
+        let apart = uart(0, 0x40013800) + &uart(1, 0x40020000) + &uart(2, 0x40014000);
+        #[cfg(feature = "strict")]
+        device(&apart).expect_err("USART1 and UART1 do not overlap");
+        #[cfg(not(feature = "strict"))]
+        device(&apart).expect("alternates are only checked with `strict`");
+    }
+
+    #[test]
+    fn address_blocks_with_device_size() {
+        let device = |peripheral_size: &str| {
+            crate::parse(&format!(
+                "
+                <device>
+                    <name>TEST</name>
+                    <size>32</size>
+                    <peripherals>
+                        <peripheral>
+                            <name>TIMER0</name>
+                            <baseAddress>0x40000000</baseAddress>
+                            {}
+                            <addressBlock>
+                                <offset>0x0</offset>
+                                <size>0x4</size>
+                                <usage>registers</usage>
+                            </addressBlock>
+                            <registers>
+                                <register>
+                                    <name>CR</name>
+                                    <addressOffset>0x2</addressOffset>
+                                </register>
+                            </registers>
+                        </peripheral>
+                    </peripherals>
+                </device>
+                ",
+                peripheral_size
+            ))
+        };
+
+        device("<size>8</size>").unwrap();
+        // `CR` takes 4 bytes of the device size and crosses the end of the block
+        #[cfg(feature = "strict")]
+        device("").expect_err("CR is outside of the address block");
+        #[cfg(not(feature = "strict"))]
+        device("").expect("address blocks are only checked with `strict`");
+    }
+
+    #[test]
+    fn decode_encode() {
+        // Namespace attributes of `device` are not comparable with `run_test`,
+        // so check that the device survives a parse/encode round trip instead
+        let device = crate::parse(
+            "
+                <device schemaVersion=\"1.3\">
+                    <vendor>ARM Ltd.</vendor>
+                    <vendorID>ARM</vendorID>
+                    <name>ARMCM33</name>
+                    <series>ARMCM33</series>
+                    <version>1.0</version>
+                    <description>ARM 32-bit v8-M Mainline device</description>
+                    <licenseText>Licensed under the Apache License</licenseText>
+                    <headerSystemFilename>system_ARMCM33</headerSystemFilename>
+                    <headerDefinitionsPrefix>ARM_</headerDefinitionsPrefix>
+                    <addressUnitBits>8</addressUnitBits>
+                    <width>32</width>
+                    <peripherals>
+                        <peripheral>
+                            <name>TIMER0</name>
+                            <baseAddress>0x40000000</baseAddress>
+                        </peripheral>
+                    </peripherals>
+                    <vendorExtensions>
+                        <clocks><clock name=\"HFXO\"/></clocks>
+                    </vendorExtensions>
+                </device>
+            ",
+        )
+        .unwrap();
+        let expected = DeviceBuilder::default()
+            .vendor(Some("ARM Ltd.".to_string()))
+            .vendor_id(Some("ARM".to_string()))
+            .name("ARMCM33".to_string())
+            .series(Some("ARMCM33".to_string()))
+            .schema_version(Some("1.3".to_string()))
+            .version(Some("1.0".to_string()))
+            .description(Some("ARM 32-bit v8-M Mainline device".to_string()))
+            .license_text(Some("Licensed under the Apache License".to_string()))
+            .header_system_filename(Some("system_ARMCM33".to_string()))
+            .header_definitions_prefix(Some("ARM_".to_string()))
+            .address_unit_bits(Some(8))
+            .width(Some(32))
+            .peripherals(vec![Peripheral::Single(
+                PeripheralInfoBuilder::default()
+                    .name("TIMER0".to_string())
+                    .base_address(0x40000000)
+                    .build()
+                    .unwrap(),
+            )])
+            .build()
+            .unwrap();
+        assert_eq!(
+            crate::parse(&crate::encode(&device).unwrap()).unwrap(),
+            device
+        );
+        let mut device = device;
+        let extensions = device.vendor_extensions.take().unwrap();
+        assert_eq!(
+            extensions.to_xml().unwrap(),
+            "<vendorExtensions><clocks><clock name=\"HFXO\" /></clocks></vendorExtensions>"
+        );
+        assert_eq!(device, expected);
+    }
+
+    #[test]
+    fn encode_order() {
+        let device = crate::parse(
+            "
+                <device>
+                    <name>TEST</name>
+                    <licenseText>License</licenseText>
+                    <cpu>
+                        <name>CM0</name>
+                        <revision>r0p0</revision>
+                        <endian>little</endian>
+                        <mpuPresent>false</mpuPresent>
+                        <fpuPresent>false</fpuPresent>
+                        <nvicPrioBits>2</nvicPrioBits>
+                        <vendorSystickConfig>false</vendorSystickConfig>
+                    </cpu>
+                    <headerSystemFilename>system_TEST</headerSystemFilename>
+                    <addressUnitBits>8</addressUnitBits>
+                    <width>32</width>
+                    <size>32</size>
+                    <peripherals>
+                        <peripheral>
+                            <name>TIMER0</name>
+                            <baseAddress>0x40000000</baseAddress>
+                        </peripheral>
+                    </peripherals>
+                </device>
+            ",
+        )
+        .unwrap();
+        let names: Vec<_> = device
+            .encode()
+            .unwrap()
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .map(|e| e.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "name",
+                "licenseText",
+                "cpu",
+                "headerSystemFilename",
+                "addressUnitBits",
+                "width",
+                "size",
+                "peripherals"
+            ]
+        );
+    }
+
+    #[test]
+    fn inherit_register_properties() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>TEST</name>
+                <size>32</size>
+                <access>read-write</access>
+                <protection>s</protection>
+                <resetValue>0</resetValue>
+                <peripherals>
+                    <peripheral>
+                        <name>TIMER0</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <access>read-only</access>
+                        <registers>
+                            <cluster>
+                                <name>CH</name>
+                                <addressOffset>0x10</addressOffset>
+                                <size>16</size>
+                                <register>
+                                    <name>CNT</name>
+                                    <addressOffset>0x0</addressOffset>
+                                    <resetValue>0x10</resetValue>
+                                </register>
+                            </cluster>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let peripheral = &device.peripherals[0];
+        let registers = peripheral.registers.as_ref().unwrap();
+        let (cluster, cnt) = match &registers[0] {
+            RegisterCluster::Cluster(c) => match &c.children[0] {
+                RegisterCluster::Register(r) => (c, r),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        };
+        let props = device.effective_properties(peripheral, &[cluster], cnt);
+        assert_eq!(props.size, Some(16));
+        assert_eq!(props.access, Some(Access::ReadOnly));
+        assert_eq!(props.reset_value, Some(0x10));
+        assert_eq!(props.reset_mask, None);
+        assert_eq!(props.protection, Some(Protection::Secure));
+
+        let device = device.inherit_register_properties();
+        let regs: Vec<_> = device.peripherals[0].reg_iter().collect();
+        assert_eq!(regs[0].size, Some(16));
+        assert_eq!(regs[1].size, Some(32));
+        assert_eq!(regs[1].access, Some(Access::ReadOnly));
+        assert_eq!(regs[1].reset_value, Some(0));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use crate::elementext::ElementExt;
+use xmltree::{Element, XMLNode};
+
+use crate::encode::Encode;
+use crate::error::*;
+
+use crate::new_element;
+use crate::svd::{enumeratedvalue::EnumeratedValue, enumeratedvalues::EnumeratedValuesError};
+use crate::types::Parse;
+
+/// Names of the elements of an array, used to generate an enumeration in the device header file
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct DimArrayIndex {
+    /// Specify the base name of enumerations
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub header_enum_name: Option<String>,
+
+    /// Specify the values contained in the enumeration
+    pub values: Vec<EnumeratedValue>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DimArrayIndexBuilder {
+    header_enum_name: Option<String>,
+    values: Option<Vec<EnumeratedValue>>,
+}
+
+impl From<DimArrayIndex> for DimArrayIndexBuilder {
+    fn from(d: DimArrayIndex) -> Self {
+        Self {
+            header_enum_name: d.header_enum_name,
+            values: Some(d.values),
+        }
+    }
+}
+
+impl DimArrayIndexBuilder {
+    pub fn header_enum_name(mut self, value: Option<String>) -> Self {
+        self.header_enum_name = value;
+        self
+    }
+    pub fn values(mut self, value: Vec<EnumeratedValue>) -> Self {
+        self.values = Some(value);
+        self
+    }
+    pub fn build(self) -> Result<DimArrayIndex, Error> {
+        (DimArrayIndex {
+            header_enum_name: self.header_enum_name,
+            values: self.values.unwrap_or_default(),
+        })
+        .validate()
+        .map_err(Error::from)
+    }
+}
+
+impl DimArrayIndex {
+    fn validate(self) -> Result<Self> {
+        #[cfg(feature = "strict")]
+        {
+            if let Some(name) = self.header_enum_name.as_ref() {
+                check_name(name, "headerEnumName")?;
+            }
+        }
+        if self.values.is_empty() {
+            Err(EnumeratedValuesError::Empty.into())
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl Parse for DimArrayIndex {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        if tree.name != "dimArrayIndex" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "dimArrayIndex".to_string()).into());
+        }
+        DimArrayIndexBuilder::default()
+            .header_enum_name(tree.get_child_text_opt("headerEnumName")?)
+            .values({
+                let values: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter_map(XMLNode::as_element)
+                    .filter(|t| t.name == "enumeratedValue")
+                    .enumerate()
+                    .map(|(e, t)| {
+                        EnumeratedValue::parse(t)
+                            .with_context(|| format!("Parsing enumerated value #{}", e))
+                    })
+                    .collect();
+                values?
+            })
+            .build()
+            .map_err(Error::into_anyhow)
+    }
+}
+
+impl Encode for DimArrayIndex {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut base = Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("dimArrayIndex"),
+            attributes: HashMap::new(),
+            children: Vec::new(),
+        };
+
+        if let Some(d) = &self.header_enum_name {
+            base.children.push(XMLNode::Element(new_element(
+                "headerEnumName",
+                Some(d.clone()),
+            )));
+        }
+
+        for v in &self.values {
+            base.children.push(XMLNode::Element(v.encode()?));
+        }
+
+        Ok(base)
+    }
+}
@@ -1,5 +1,7 @@
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
+use crate::parse;
+use crate::svd::dimarrayindex::DimArrayIndex;
 use crate::types::{parse_optional, DimIndex, Parse};
 
 use crate::elementext::ElementExt;
@@ -22,14 +24,26 @@ pub struct DimElement {
     /// By default, <dimIndex> is a value starting with 0
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub dim_index: Option<Vec<String>>,
+    pub dim_index: Option<DimIndex>,
+
+    /// Specify the name of the C-type structure. If not specified, then the entry of the `name` is used
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dim_name: Option<String>,
+
+    /// Specify the enumeration names of the array indices
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dim_array_index: Option<DimArrayIndex>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DimElementBuilder {
     dim: Option<u32>,
     dim_increment: Option<u32>,
-    dim_index: Option<Vec<String>>,
+    dim_index: Option<DimIndex>,
+    dim_name: Option<String>,
+    dim_array_index: Option<DimArrayIndex>,
 }
 
 impl From<DimElement> for DimElementBuilder {
@@ -38,6 +52,8 @@ impl From<DimElement> for DimElementBuilder {
             dim: Some(d.dim),
             dim_increment: Some(d.dim_increment),
             dim_index: d.dim_index,
+            dim_name: d.dim_name,
+            dim_array_index: d.dim_array_index,
         }
     }
 }
@@ -51,11 +67,19 @@ impl DimElementBuilder {
         self.dim_increment = Some(value);
         self
     }
-    pub fn dim_index(mut self, value: Option<Vec<String>>) -> Self {
+    pub fn dim_index(mut self, value: Option<DimIndex>) -> Self {
         self.dim_index = value;
         self
     }
-    pub fn build(self) -> Result<DimElement> {
+    pub fn dim_name(mut self, value: Option<String>) -> Self {
+        self.dim_name = value;
+        self
+    }
+    pub fn dim_array_index(mut self, value: Option<DimArrayIndex>) -> Self {
+        self.dim_array_index = value;
+        self
+    }
+    pub fn build(self) -> Result<DimElement, Error> {
         Ok(DimElement {
             dim: self
                 .dim
@@ -64,10 +88,77 @@ impl DimElementBuilder {
                 .dim_increment
                 .ok_or_else(|| BuildError::Uninitialized("dim_increment".to_string()))?,
             dim_index: self.dim_index,
+            dim_name: self.dim_name,
+            dim_array_index: self.dim_array_index,
         })
     }
 }
 
+impl DimElement {
+    /// Returns an iterator over the strings substituted for the `%s` placeholder,
+    /// one per array element
+    pub fn indexes(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.len()).map_while(move |i| self.index(i))
+    }
+
+    /// Returns the number of array elements
+    pub fn len(&self) -> usize {
+        match &self.dim_index {
+            Some(indexes) => indexes.len(),
+            None => self.dim as usize,
+        }
+    }
+
+    /// Returns `true` if there are no array elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the string substituted for the `%s` placeholder of the array element `i`
+    pub fn index(&self, i: usize) -> Option<String> {
+        match &self.dim_index {
+            Some(indexes) => indexes.get(i),
+            None => Some(i)
+                .filter(|&i| i < self.dim as usize)
+                .map(|i| i.to_string()),
+        }
+    }
+
+    /// Returns the number of the array element whose `%s` placeholder is substituted by `index`
+    pub fn position(&self, index: &str) -> Option<usize> {
+        match &self.dim_index {
+            Some(indexes) => indexes.position(index),
+            None => index
+                .parse::<u32>()
+                .ok()
+                .filter(|&i| i.to_string() == index && i < self.dim)
+                .map(|i| i as usize),
+        }
+    }
+}
+
+/// Substitutes `%s` or `[%s]` placeholder in `name` with `index`
+pub(crate) fn expand_name(name: &str, index: &str) -> String {
+    name.replace("[%s]", index).replace("%s", index)
+}
+
+/// Returns the index which substituted for the placeholder of `pattern` gives `name`
+#[cfg_attr(not(feature = "strict"), allow(dead_code))]
+pub(crate) fn match_name<'a>(pattern: &str, name: &'a str) -> Option<&'a str> {
+    let placeholder = if pattern.contains("[%s]") {
+        "[%s]"
+    } else {
+        "%s"
+    };
+    let (prefix, suffix) = pattern.split_once(placeholder)?;
+    let rest = name.strip_prefix(prefix)?;
+    // The placeholder may appear again in the suffix
+    (0..=rest.len())
+        .filter(|&i| rest.is_char_boundary(i))
+        .map(|i| &rest[..i])
+        .find(|index| !index.is_empty() && expand_name(suffix, index) == rest[index.len()..])
+}
+
 impl Parse for DimElement {
     type Object = Self;
     type Error = anyhow::Error;
@@ -77,7 +168,10 @@ impl Parse for DimElement {
             .dim(tree.get_child_u32("dim")?)
             .dim_increment(tree.get_child_u32("dimIncrement")?)
             .dim_index(parse_optional::<DimIndex>("dimIndex", tree)?)
+            .dim_name(tree.get_child_text_opt("dimName")?)
+            .dim_array_index(parse::optional::<DimArrayIndex>("dimArrayIndex", tree)?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -87,15 +181,26 @@ impl Encode for DimElement {
     fn encode(&self) -> Result<Element> {
         let mut e = new_element("dimElement", None);
 
-        e.children
-            .push(new_element("dim", Some(format!("{}", self.dim))));
-        e.children.push(new_element(
+        e.children.push(XMLNode::Element(new_element(
+            "dim",
+            Some(format!("{}", self.dim)),
+        )));
+        e.children.push(XMLNode::Element(new_element(
             "dimIncrement",
             Some(format!("{}", self.dim_increment)),
-        ));
+        )));
 
         if let Some(di) = &self.dim_index {
-            e.children.push(new_element("dimIndex", Some(di.join(","))));
+            e.children.push(XMLNode::Element(di.encode()?));
+        }
+
+        if let Some(dn) = &self.dim_name {
+            e.children
+                .push(XMLNode::Element(new_element("dimName", Some(dn.clone()))));
+        }
+
+        if let Some(dai) = &self.dim_array_index {
+            e.children.push(XMLNode::Element(dai.encode()?));
         }
 
         Ok(e)
@@ -106,24 +211,108 @@ impl Encode for DimElement {
 mod tests {
     use super::*;
     use crate::run_test;
+    use crate::svd::{
+        dimarrayindex::DimArrayIndexBuilder, enumeratedvalue::EnumeratedValueBuilder,
+    };
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            DimElementBuilder::default()
-                .dim(100)
-                .dim_increment(4)
-                .dim_index(Some(vec!["10".to_string(), "20".to_string()]))
-                .build()
-                .unwrap(),
-            "<dimElement>
+        let tests = [
+            (
+                DimElementBuilder::default()
+                    .dim(100)
+                    .dim_increment(4)
+                    .dim_index(Some(DimIndex::List(vec![
+                        "10".to_string(),
+                        "20".to_string(),
+                    ])))
+                    .build()
+                    .unwrap(),
+                "<dimElement>
                 <dim>100</dim>
                 <dimIncrement>4</dimIncrement>
                 <dimIndex>10,20</dimIndex>
             </dimElement>
             ",
-        )];
+            ),
+            (
+                DimElementBuilder::default()
+                    .dim(2)
+                    .dim_increment(0x100)
+                    .dim_index(Some(DimIndex::List(vec!["A".to_string(), "B".to_string()])))
+                    .dim_name(Some("Channel_Type".to_string()))
+                    .dim_array_index(Some(
+                        DimArrayIndexBuilder::default()
+                            .header_enum_name(Some("CHANNEL".to_string()))
+                            .values(vec![
+                                EnumeratedValueBuilder::default()
+                                    .name("CH_A".to_string())
+                                    .value(Some(0))
+                                    .build()
+                                    .unwrap(),
+                                EnumeratedValueBuilder::default()
+                                    .name("CH_B".to_string())
+                                    .value(Some(1))
+                                    .build()
+                                    .unwrap(),
+                            ])
+                            .build()
+                            .unwrap(),
+                    ))
+                    .build()
+                    .unwrap(),
+                "<dimElement>
+                <dim>2</dim>
+                <dimIncrement>256</dimIncrement>
+                <dimIndex>A,B</dimIndex>
+                <dimName>Channel_Type</dimName>
+                <dimArrayIndex>
+                    <headerEnumName>CHANNEL</headerEnumName>
+                    <enumeratedValue>
+                        <name>CH_A</name>
+                        <value>0x00000000</value>
+                    </enumeratedValue>
+                    <enumeratedValue>
+                        <name>CH_B</name>
+                        <value>0x00000001</value>
+                    </enumeratedValue>
+                </dimArrayIndex>
+            </dimElement>
+            ",
+            ),
+        ];
 
         run_test::<DimElement>(&tests[..]);
     }
+
+    #[test]
+    fn element_names() {
+        let dim = |index: Option<&str>| {
+            DimElementBuilder::default()
+                .dim(4)
+                .dim_increment(4)
+                .dim_index(index.map(|i| DimIndex::parse_str(i).unwrap()))
+                .build()
+                .unwrap()
+        };
+        let numbers = dim(None);
+        assert_eq!(numbers.index(3), Some("3".to_string()));
+        assert_eq!(numbers.index(4), None);
+        assert_eq!(numbers.position("3"), Some(3));
+        assert_eq!(numbers.position("03"), None);
+        let range = dim(Some("4-7"));
+        assert_eq!(range.index(0), Some("4".to_string()));
+        assert_eq!(range.position("7"), Some(3));
+        assert_eq!(range.position("3"), None);
+        let letters = dim(Some("A-D"));
+        assert_eq!(letters.index(1), Some("B".to_string()));
+        assert_eq!(letters.position("D"), Some(3));
+        assert_eq!(letters.position("E"), None);
+
+        assert_eq!(match_name("UART%s", "UART10"), Some("10"));
+        assert_eq!(match_name("DATA[%s]", "DATA2"), Some("2"));
+        assert_eq!(match_name("CH%s_CR%s", "CH1_CR1"), Some("1"));
+        assert_eq!(match_name("UART%s", "USART1"), None);
+        assert_eq!(match_name("UART", "UART"), None);
+    }
 }
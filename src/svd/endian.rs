@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::elementext::ElementExt;
 use crate::encode::Encode;
@@ -22,7 +22,7 @@ impl Parse for Endian {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         match &text[..] {
             "little" => Ok(Endian::Little),
@@ -51,8 +51,7 @@ impl Encode for Endian {
             namespaces: None,
             name: String::from("endian"),
             attributes: HashMap::new(),
-            children: Vec::new(),
-            text: Some(text),
+            children: vec![XMLNode::Text(text)],
         })
     }
 }
@@ -64,7 +63,7 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![
+        let tests = [
             (Endian::Little, "<endian>little</endian>"),
             (Endian::Big, "<endian>big</endian>"),
             (Endian::Selectable, "<endian>selectable</endian>"),
@@ -1,8 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use crate::parse;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -26,6 +25,12 @@ pub struct EnumeratedValue {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub value: Option<u64>,
 
+    /// Significant bits of `value`, the others are don't care bits written as `x`.
+    /// `None` if all bits are significant
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub mask: Option<u64>,
+
     /// Defines the name and description for all other values that are not listed explicitly
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -38,6 +43,10 @@ pub enum EnumeratedValueError {
     AbsentValue(Option<u64>, Option<bool>),
     #[error("Value {0} out of range {1:?}")]
     OutOfRange(u64, core::ops::Range<u64>),
+    #[error("EnumeratedValue has a mask {0:#x} but no `value`")]
+    MaskWithoutValue(u64),
+    #[error("Value {0:#x} has bits set outside of mask {1:#x}")]
+    OutsideMask(u64, u64),
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -45,6 +54,7 @@ pub struct EnumeratedValueBuilder {
     name: Option<String>,
     description: Option<String>,
     value: Option<u64>,
+    mask: Option<u64>,
     is_default: Option<bool>,
 }
 
@@ -54,6 +64,7 @@ impl From<EnumeratedValue> for EnumeratedValueBuilder {
             name: Some(e.name),
             description: e.description,
             value: e.value,
+            mask: e.mask,
             is_default: e.is_default,
         }
     }
@@ -72,21 +83,27 @@ impl EnumeratedValueBuilder {
         self.value = value;
         self
     }
+    pub fn mask(mut self, value: Option<u64>) -> Self {
+        self.mask = value;
+        self
+    }
     #[allow(clippy::wrong_self_convention)]
     pub fn is_default(mut self, value: Option<bool>) -> Self {
         self.is_default = value;
         self
     }
-    pub fn build(self) -> Result<EnumeratedValue> {
+    pub fn build(self) -> Result<EnumeratedValue, Error> {
         (EnumeratedValue {
             name: self
                 .name
                 .ok_or_else(|| BuildError::Uninitialized("name".to_string()))?,
             description: self.description,
             value: self.value,
+            mask: self.mask,
             is_default: self.is_default,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -94,31 +111,89 @@ impl EnumeratedValue {
     fn validate(self) -> Result<Self> {
         #[cfg(feature = "strict")]
         check_name(&self.name, "name")?;
+        match (self.value, self.mask) {
+            (None, Some(mask)) => return Err(EnumeratedValueError::MaskWithoutValue(mask).into()),
+            // don't care bits are always stored as 0
+            (Some(value), Some(mask)) if value & !mask != 0 => {
+                return Err(EnumeratedValueError::OutsideMask(value, mask).into())
+            }
+            _ => {}
+        }
         match (&self.value, &self.is_default) {
             (Some(_), None) | (None, Some(_)) => Ok(self),
             _ => Err(EnumeratedValueError::AbsentValue(self.value, self.is_default).into()),
         }
     }
     pub(crate) fn check_range(&self, range: &core::ops::Range<u64>) -> Result<()> {
-        match &self.value {
-            Some(x) if !range.contains(x) => {
-                Err(EnumeratedValueError::OutOfRange(*x, range.clone()).into())
+        // the largest value covered by the pattern
+        match self.value.map(|v| v | self.dont_care()) {
+            Some(x) if !range.contains(&x) => {
+                Err(EnumeratedValueError::OutOfRange(x, range.clone()).into())
             }
             _ => Ok(()),
         }
     }
+
+    /// Bits of `value` which can take any value
+    pub fn dont_care(&self) -> u64 {
+        self.mask.map(|m| !m).unwrap_or(0)
+    }
+
+    /// Returns `true` if `value` is described by this enumerated value.
+    /// Don't care bits are ignored, `isDefault` values never match
+    pub fn matches(&self, value: u64) -> bool {
+        match self.value {
+            Some(v) => (value | self.dont_care()) == (v | self.dont_care()),
+            None => false,
+        }
+    }
+
+    /// Iterates over all concrete values described by this enumerated value,
+    /// `2^n` values for `n` don't care bits
+    pub fn values(&self) -> impl Iterator<Item = u64> {
+        let value = self.value;
+        let dont_care = self.dont_care();
+        // iterate over all subsets of the don't care bits
+        core::iter::successors(value.map(|_| 0), move |&bits: &u64| {
+            Some(bits.wrapping_sub(dont_care) & dont_care).filter(|_| bits != dont_care)
+        })
+        .map(move |bits| value.unwrap_or_default() | bits)
+    }
+}
+
+/// Parses a `value` which can contain don't care bits (`#01x1` or `0b01x1`)
+/// into the value with don't care bits set to 0 and the mask of significant bits
+fn parse_value(tree: &Element) -> Result<(u64, Option<u64>)> {
+    let text = tree.get_content()?;
+    let lower = text.to_lowercase();
+    let digits = lower
+        .strip_prefix('#')
+        .or_else(|| lower.strip_prefix("0b"))
+        .unwrap_or_default();
+    if !digits.contains('x') {
+        return Ok((u64::parse(tree)?, None));
+    }
+    let binary = |x: &str| {
+        u64::from_str_radix(&digits.replace('x', x), 2).with_context(|| format!("{} invalid", text))
+    };
+    let value = binary("0")?;
+    let dont_care = binary("1")? & !value;
+    Ok((value, Some(!dont_care)))
 }
 
 impl EnumeratedValue {
     fn _parse(tree: &Element, name: String) -> Result<Self> {
+        let value = tree.get_child("value").map(parse_value).transpose()?;
         EnumeratedValueBuilder::default()
             .name(name)
             .description(tree.get_child_text_opt("description")?)
             // TODO: this .ok() approach is simple, but does not expose errors parsing child objects.
             // Suggest refactoring all parse::type methods to return result so parse::optional works.
-            .value(parse::optional::<u64>("value", tree)?)
+            .value(value.map(|(v, _)| v))
+            .mask(value.and_then(|(_, m)| m))
             .is_default(tree.get_child_bool("isDefault").ok())
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 impl Parse for EnumeratedValue {
@@ -128,11 +203,11 @@ impl Parse for EnumeratedValue {
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "enumeratedValue" {
             return Err(
-                SVDError::NotExpectedTag(tree.clone(), "enumeratedValue".to_string()).into(),
+                SVDError::NotExpectedTag(tree.into(), "enumeratedValue".to_string()).into(),
             );
         }
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In enumerated value `{}`", name))
+        Self::_parse(tree, name.clone()).in_element("enumeratedValue", &name)
     }
 }
 
@@ -146,23 +221,43 @@ impl Encode for EnumeratedValue {
             namespaces: None,
             name: String::from("enumeratedValue"),
             attributes: HashMap::new(),
-            children: vec![new_element("name", Some(self.name.clone()))],
-            text: None,
+            children: vec![XMLNode::Element(new_element(
+                "name",
+                Some(self.name.clone()),
+            ))],
         };
 
         if let Some(d) = &self.description {
             let s = (*d).clone();
-            base.children.push(new_element("description", Some(s)));
+            base.children
+                .push(XMLNode::Element(new_element("description", Some(s))));
         };
 
         if let Some(v) = &self.value {
+            let dont_care = self.dont_care();
+            let value = if dont_care == 0 {
+                format!("0x{:08.x}", *v)
+            } else {
+                let width = 64 - (*v | dont_care).leading_zeros();
+                let digits: String = (0..width)
+                    .rev()
+                    .map(|i| match (dont_care >> i & 1, *v >> i & 1) {
+                        (1, _) => 'x',
+                        (_, 1) => '1',
+                        _ => '0',
+                    })
+                    .collect();
+                format!("#{}", digits)
+            };
             base.children
-                .push(new_element("value", Some(format!("0x{:08.x}", *v))));
+                .push(XMLNode::Element(new_element("value", Some(value))));
         };
 
         if let Some(v) = &self.is_default {
-            base.children
-                .push(new_element("isDefault", Some(format!("{}", v))));
+            base.children.push(XMLNode::Element(new_element(
+                "isDefault",
+                Some(format!("{}", v)),
+            )));
         };
 
         Ok(base)
@@ -176,24 +271,86 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            EnumeratedValueBuilder::default()
-                .name("WS0".to_string())
-                .description(Some(
-                    "Zero wait-states inserted in fetch or read transfers".to_string(),
-                ))
-                .value(Some(0))
-                .build()
-                .unwrap(),
-            "
+        let tests = [
+            (
+                EnumeratedValueBuilder::default()
+                    .name("WS0".to_string())
+                    .description(Some(
+                        "Zero wait-states inserted in fetch or read transfers".to_string(),
+                    ))
+                    .value(Some(0))
+                    .build()
+                    .unwrap(),
+                "
                 <enumeratedValue>
                     <name>WS0</name>
                     <description>Zero wait-states inserted in fetch or read transfers</description>
                     <value>0x00000000</value>
                 </enumeratedValue>
             ",
-        )];
+            ),
+            (
+                EnumeratedValueBuilder::default()
+                    .name("ODD".to_string())
+                    .value(Some(0b101))
+                    .mask(Some(!0b1010))
+                    .build()
+                    .unwrap(),
+                "
+                <enumeratedValue>
+                    <name>ODD</name>
+                    <value>#x1x1</value>
+                </enumeratedValue>
+            ",
+            ),
+        ];
 
         run_test::<EnumeratedValue>(&tests[..]);
     }
+
+    #[test]
+    fn dont_care() {
+        let value = |text: &str| {
+            EnumeratedValue::parse(
+                &Element::parse(
+                    format!(
+                        "<enumeratedValue><name>V</name><value>{}</value></enumeratedValue>",
+                        text
+                    )
+                    .as_bytes(),
+                )
+                .unwrap(),
+            )
+        };
+
+        let ev = value("0b01X1").unwrap();
+        assert_eq!(ev.value, Some(0b0101));
+        assert_eq!(ev.dont_care(), 0b0010);
+        assert!(ev.matches(0b0101));
+        assert!(ev.matches(0b0111));
+        assert!(!ev.matches(0b0100));
+        assert!(!ev.matches(0b1101));
+        assert_eq!(ev.values().collect::<Vec<_>>(), vec![0b0101, 0b0111]);
+        assert_eq!(
+            value("#xx").unwrap().values().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        ev.check_range(&(0..8)).unwrap();
+        ev.check_range(&(0..4)).unwrap_err();
+        let any = value(&format!("#{}", "x".repeat(64))).unwrap();
+        assert_eq!(any.values().nth(3), Some(3));
+
+        let ev = value("0x5").unwrap();
+        assert_eq!(ev.mask, None);
+        assert_eq!(ev.values().collect::<Vec<_>>(), vec![5]);
+        assert!(ev.matches(5));
+
+        value("#1x2").unwrap_err();
+        EnumeratedValueBuilder::default()
+            .name("V".to_string())
+            .mask(Some(!1))
+            .is_default(Some(true))
+            .build()
+            .unwrap_err();
+    }
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -20,6 +20,11 @@ pub struct EnumeratedValues {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub name: Option<String>,
 
+    /// Identifier for the enumeration in the C header file, overrides `name`
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub header_enum_name: Option<String>,
+
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub usage: Option<Usage>,
@@ -42,6 +47,7 @@ pub enum EnumeratedValuesError {
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct EnumeratedValuesBuilder {
     name: Option<String>,
+    header_enum_name: Option<String>,
     usage: Option<Usage>,
     derived_from: Option<String>,
     values: Option<Vec<EnumeratedValue>>,
@@ -51,6 +57,7 @@ impl From<EnumeratedValues> for EnumeratedValuesBuilder {
     fn from(e: EnumeratedValues) -> Self {
         Self {
             name: e.name,
+            header_enum_name: e.header_enum_name,
             usage: e.usage,
             derived_from: e.derived_from,
             values: Some(e.values),
@@ -63,6 +70,10 @@ impl EnumeratedValuesBuilder {
         self.name = value;
         self
     }
+    pub fn header_enum_name(mut self, value: Option<String>) -> Self {
+        self.header_enum_name = value;
+        self
+    }
     pub fn usage(mut self, value: Option<Usage>) -> Self {
         self.usage = value;
         self
@@ -75,14 +86,16 @@ impl EnumeratedValuesBuilder {
         self.values = Some(value);
         self
     }
-    pub fn build(self) -> Result<EnumeratedValues> {
+    pub fn build(self) -> Result<EnumeratedValues, Error> {
         (EnumeratedValues {
             name: self.name,
+            header_enum_name: self.header_enum_name,
             usage: self.usage,
             derived_from: self.derived_from,
             values: self.values.unwrap_or_default(),
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -93,6 +106,9 @@ impl EnumeratedValues {
             if let Some(name) = self.name.as_ref() {
                 check_name(name, "name")?;
             }
+            if let Some(name) = self.header_enum_name.as_ref() {
+                check_name(name, "headerEnumName")?;
+            }
         }
         if let Some(_dname) = self.derived_from.as_ref() {
             #[cfg(feature = "strict")]
@@ -110,6 +126,30 @@ impl EnumeratedValues {
         }
         Ok(())
     }
+
+    /// Returns the usage of the enumeration, `read-write` if not specified
+    pub fn usage(&self) -> Usage {
+        self.usage.unwrap_or(Usage::ReadWrite)
+    }
+
+    /// Returns the enumerated value describing `value`,
+    /// or the `isDefault` one if no value matches
+    pub fn get_by_value(&self, value: u64) -> Option<&EnumeratedValue> {
+        self.values
+            .iter()
+            .find(|v| v.matches(value))
+            .or_else(|| self.default_value())
+    }
+
+    /// Returns the enumerated value with the given name
+    pub fn get_by_name(&self, name: &str) -> Option<&EnumeratedValue> {
+        self.values.iter().find(|v| v.name == name)
+    }
+
+    /// Returns the `isDefault` enumerated value
+    pub fn default_value(&self) -> Option<&EnumeratedValue> {
+        self.values.iter().find(|v| v.is_default == Some(true))
+    }
 }
 
 impl Parse for EnumeratedValues {
@@ -117,15 +157,21 @@ impl Parse for EnumeratedValues {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        assert_eq!(tree.name, "enumeratedValues");
+        if tree.name != "enumeratedValues" {
+            return Err(
+                SVDError::NotExpectedTag(tree.into(), "enumeratedValues".to_string()).into(),
+            );
+        }
         EnumeratedValuesBuilder::default()
             .name(tree.get_child_text_opt("name")?)
+            .header_enum_name(tree.get_child_text_opt("headerEnumName")?)
             .usage(parse::optional::<Usage>("usage", tree)?)
             .derived_from(tree.attributes.get("derivedFrom").map(|s| s.to_owned()))
             .values({
                 let values: Result<Vec<_>, _> = tree
                     .children
                     .iter()
+                    .filter_map(XMLNode::as_element)
                     .filter(|t| {
                         ["name", "headerEnumName", "usage"]
                             .iter()
@@ -138,7 +184,7 @@ impl Parse for EnumeratedValues {
                                 .with_context(|| format!("Parsing enumerated value #{}", e))
                         } else {
                             Err(
-                                SVDError::NotExpectedTag(t.clone(), "enumeratedValue".to_string())
+                                SVDError::NotExpectedTag(t.into(), "enumeratedValue".to_string())
                                     .into(),
                             )
                         }
@@ -147,6 +193,7 @@ impl Parse for EnumeratedValues {
                 values?
             })
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -161,15 +208,22 @@ impl Encode for EnumeratedValues {
             name: String::from("enumeratedValues"),
             attributes: HashMap::new(),
             children: Vec::new(),
-            text: None,
         };
 
         if let Some(d) = &self.name {
-            base.children.push(new_element("name", Some((*d).clone())));
+            base.children
+                .push(XMLNode::Element(new_element("name", Some((*d).clone()))));
+        };
+
+        if let Some(v) = &self.header_enum_name {
+            base.children.push(XMLNode::Element(new_element(
+                "headerEnumName",
+                Some(v.clone()),
+            )));
         };
 
         if let Some(v) = &self.usage {
-            base.children.push(v.encode()?);
+            base.children.push(XMLNode::Element(v.encode()?));
         };
 
         if let Some(v) = &self.derived_from {
@@ -178,7 +232,7 @@ impl Encode for EnumeratedValues {
         }
 
         for v in &self.values {
-            base.children.push(v.encode()?);
+            base.children.push(XMLNode::Element(v.encode()?));
         }
 
         Ok(base)
@@ -274,4 +328,42 @@ mod tests {
         parse(value.clone() + "<enumeratedValues></enumeratedValues>")
             .expect_err("<enumeratedValues> in invalid here");
     }
+
+    #[test]
+    fn lookup() {
+        let tree = Element::parse(
+            "
+            <enumeratedValues>
+                <name>MODE</name>
+                <headerEnumName>MODE_Enum</headerEnumName>
+                <usage>read</usage>
+                <enumeratedValue>
+                    <name>OFF</name>
+                    <value>0</value>
+                </enumeratedValue>
+                <enumeratedValue>
+                    <name>ON</name>
+                    <value>#1x</value>
+                </enumeratedValue>
+                <enumeratedValue>
+                    <name>RESERVED</name>
+                    <isDefault>true</isDefault>
+                </enumeratedValue>
+            </enumeratedValues>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let evs = EnumeratedValues::parse(&tree).unwrap();
+        assert_eq!(evs.header_enum_name, Some("MODE_Enum".to_string()));
+        assert_eq!(evs.usage(), Usage::Read);
+        assert_eq!(evs.get_by_value(0).unwrap().name, "OFF");
+        assert_eq!(evs.get_by_value(3).unwrap().name, "ON");
+        assert_eq!(evs.get_by_value(1).unwrap().name, "RESERVED");
+        assert_eq!(evs.get_by_name("ON").unwrap().value, Some(2));
+        assert!(evs.get_by_name("STANDBY").is_none());
+
+        let tree2 = evs.encode().unwrap();
+        assert_eq!(EnumeratedValues::parse(&tree2).unwrap(), evs);
+    }
 }
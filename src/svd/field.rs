@@ -1,3 +1,4 @@
+use core::convert::TryFrom;
 use core::ops::{Deref, DerefMut};
 
 use xmltree::Element;
@@ -8,7 +9,10 @@ use crate::elementext::ElementExt;
 
 use crate::encode::Encode;
 use crate::error::*;
-use crate::svd::{dimelement::DimElement, fieldinfo::FieldInfo};
+use crate::svd::{
+    dimelement::{expand_name, DimElement},
+    fieldinfo::FieldInfo,
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -37,12 +41,39 @@ impl DerefMut for Field {
     }
 }
 
+impl Field {
+    /// Returns one `Single` field per element of the array.
+    /// A `Single` field is returned unchanged.
+    /// Expansion stops at the first element whose bit offset overflows
+    pub fn expand(&self) -> Vec<Field> {
+        match self {
+            Field::Single(info) => vec![Field::Single(info.clone())],
+            Field::Array(info, array_info) => array_info
+                .indexes()
+                .enumerate()
+                .map_while(|(i, index)| {
+                    let increment = array_info
+                        .dim_increment
+                        .checked_mul(u32::try_from(i).ok()?)?;
+                    let offset = info.bit_range.offset.checked_add(increment)?;
+                    let mut info = info.clone();
+                    info.name = expand_name(&info.name, &index);
+                    info.bit_range.offset = offset;
+                    Some(Field::Single(info))
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Parse for Field {
     type Object = Self;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        assert_eq!(tree.name, "field");
+        if tree.name != "field" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "field".to_string()).into());
+        }
 
         let info = FieldInfo::parse(tree)?;
 
@@ -50,7 +81,14 @@ impl Parse for Field {
             let array_info = DimElement::parse(tree)?;
             check_has_placeholder(&info.name, "field")?;
             if let Some(indices) = &array_info.dim_index {
-                assert_eq!(array_info.dim as usize, indices.len())
+                if array_info.dim as usize != indices.len() {
+                    return Err(SVDError::DimIndexLength(
+                        tree.into(),
+                        array_info.dim,
+                        indices.len(),
+                    )
+                    .into());
+                }
             }
             Ok(Field::Array(info, array_info))
         } else {
@@ -83,9 +121,10 @@ mod tests {
     use crate::fieldinfo::FieldInfoBuilder;
 
     use crate::run_test;
+    use crate::types::DimIndex;
     #[test]
     fn decode_encode() {
-        let tests = vec![(
+        let tests = [(
             Field::Array(
                 FieldInfoBuilder::default()
                     .name("MODE%s".to_string())
@@ -100,7 +139,10 @@ mod tests {
                 DimElementBuilder::default()
                     .dim(2)
                     .dim_increment(4)
-                    .dim_index(Some(vec!["10".to_string(), "20".to_string()]))
+                    .dim_index(Some(DimIndex::List(vec![
+                        "10".to_string(),
+                        "20".to_string(),
+                    ])))
                     .build()
                     .unwrap(),
             ),
@@ -117,4 +159,33 @@ mod tests {
         )];
         run_test::<Field>(&tests[..]);
     }
+    #[test]
+    fn expand() {
+        let field = Field::Array(
+            FieldInfoBuilder::default()
+                .name("MODE%s".to_string())
+                .bit_range(BitRange {
+                    offset: 8,
+                    width: 2,
+                    range_type: BitRangeType::OffsetWidth,
+                })
+                .build()
+                .unwrap(),
+            DimElementBuilder::default()
+                .dim(2)
+                .dim_increment(2)
+                .dim_index(Some(DimIndex::List(vec!["A".to_string(), "B".to_string()])))
+                .build()
+                .unwrap(),
+        );
+        let names: Vec<_> = field
+            .expand()
+            .iter()
+            .map(|f| (f.name.clone(), f.bit_range.offset))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("MODEA".to_string(), 8), ("MODEB".to_string(), 10)]
+        );
+    }
 }
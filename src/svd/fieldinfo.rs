@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -12,7 +12,8 @@ use crate::types::Parse;
 
 use crate::svd::{
     access::Access, bitrange::BitRange, enumeratedvalues::EnumeratedValues,
-    modifiedwritevalues::ModifiedWriteValues, writeconstraint::WriteConstraint,
+    modifiedwritevalues::ModifiedWriteValues, readaction::ReadAction, usage::Usage,
+    vendorextensions::VendorExtensions, writeconstraint::WriteConstraint,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -53,6 +54,16 @@ pub struct FieldInfo {
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub modified_write_values: Option<ModifiedWriteValues>,
+
+    /// Specify the side effect following a read operation
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub read_action: Option<ReadAction>,
+
+    /// Vendor specific extensions of the field, kept as raw XML
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_extensions: Option<VendorExtensions>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -65,6 +76,8 @@ pub struct FieldInfoBuilder {
     enumerated_values: Option<Vec<EnumeratedValues>>,
     write_constraint: Option<WriteConstraint>,
     modified_write_values: Option<ModifiedWriteValues>,
+    read_action: Option<ReadAction>,
+    vendor_extensions: Option<VendorExtensions>,
 }
 
 impl From<FieldInfo> for FieldInfoBuilder {
@@ -78,6 +91,8 @@ impl From<FieldInfo> for FieldInfoBuilder {
             enumerated_values: Some(f.enumerated_values),
             write_constraint: f.write_constraint,
             modified_write_values: f.modified_write_values,
+            read_action: f.read_action,
+            vendor_extensions: f.vendor_extensions,
         }
     }
 }
@@ -115,7 +130,15 @@ impl FieldInfoBuilder {
         self.modified_write_values = value;
         self
     }
-    pub fn build(self) -> Result<FieldInfo> {
+    pub fn read_action(mut self, value: Option<ReadAction>) -> Self {
+        self.read_action = value;
+        self
+    }
+    pub fn vendor_extensions(mut self, value: Option<VendorExtensions>) -> Self {
+        self.vendor_extensions = value;
+        self
+    }
+    pub fn build(self) -> Result<FieldInfo, Error> {
         (FieldInfo {
             name: self
                 .name
@@ -129,8 +152,11 @@ impl FieldInfoBuilder {
             enumerated_values: self.enumerated_values.unwrap_or_default(),
             write_constraint: self.write_constraint,
             modified_write_values: self.modified_write_values,
+            read_action: self.read_action,
+            vendor_extensions: self.vendor_extensions,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -160,16 +186,40 @@ impl FieldInfo {
     }
 }
 
+impl FieldInfo {
+    /// Returns the enumerated values describing values read from the field
+    pub fn read_enumerated_values(&self) -> Option<&EnumeratedValues> {
+        self.enumerated_values_for(Usage::Read)
+    }
+
+    /// Returns the enumerated values describing values written to the field
+    pub fn write_enumerated_values(&self) -> Option<&EnumeratedValues> {
+        self.enumerated_values_for(Usage::Write)
+    }
+
+    // An enumeration specific to `usage` is preferred over a `read-write` one
+    fn enumerated_values_for(&self, usage: Usage) -> Option<&EnumeratedValues> {
+        self.enumerated_values
+            .iter()
+            .find(|ev| ev.usage() == usage)
+            .or_else(|| {
+                self.enumerated_values
+                    .iter()
+                    .find(|ev| ev.usage() == Usage::ReadWrite)
+            })
+    }
+}
+
 impl Parse for FieldInfo {
     type Object = Self;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "field" {
-            return Err(SVDError::NotExpectedTag(tree.clone(), "field".to_string()).into());
+            return Err(SVDError::NotExpectedTag(tree.into(), "field".to_string()).into());
         }
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In field `{}`", name))
+        Self::_parse(tree, name.clone()).in_element("field", &name)
     }
 }
 
@@ -186,8 +236,9 @@ impl FieldInfo {
                 let values: Result<Vec<_>, _> = tree
                     .children
                     .iter()
+                    .filter_map(XMLNode::as_element)
                     .filter(|t| t.name == "enumeratedValues")
-                    .map(|t| EnumeratedValues::parse(t))
+                    .map(EnumeratedValues::parse)
                     .collect();
                 values?
             })
@@ -196,7 +247,13 @@ impl FieldInfo {
                 "modifiedWriteValues",
                 tree,
             )?)
+            .read_action(parse::optional::<ReadAction>("readAction", tree)?)
+            .vendor_extensions(parse::optional::<VendorExtensions>(
+                "vendorExtensions",
+                tree,
+            )?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -216,8 +273,7 @@ impl Encode for FieldInfo {
             namespaces: None,
             name: String::from("field"),
             attributes: HashMap::new(),
-            children,
-            text: None,
+            children: children.into_iter().map(XMLNode::Element).collect(),
         };
 
         if let Some(v) = &self.derived_from {
@@ -226,24 +282,34 @@ impl Encode for FieldInfo {
         }
 
         // Add bit range
-        elem.children.append(&mut self.bit_range.encode()?);
+        elem.children
+            .extend(self.bit_range.encode()?.into_iter().map(XMLNode::Element));
 
         if let Some(v) = &self.access {
-            elem.children.push(v.encode()?);
+            elem.children.push(XMLNode::Element(v.encode()?));
         };
 
-        let enumerated_values: Result<Vec<Element>> =
-            self.enumerated_values.iter().map(|v| v.encode()).collect();
-        elem.children.append(&mut enumerated_values?);
+        if let Some(v) = &self.modified_write_values {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        };
 
         if let Some(v) = &self.write_constraint {
-            elem.children.push(v.encode()?);
+            elem.children.push(XMLNode::Element(v.encode()?));
         };
 
-        if let Some(v) = &self.modified_write_values {
-            elem.children.push(v.encode()?);
+        if let Some(v) = &self.read_action {
+            elem.children.push(XMLNode::Element(v.encode()?));
         };
 
+        let enumerated_values: Result<Vec<Element>> =
+            self.enumerated_values.iter().map(|v| v.encode()).collect();
+        elem.children
+            .extend(enumerated_values?.into_iter().map(XMLNode::Element));
+
+        if let Some(v) = &self.vendor_extensions {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        }
+
         Ok(elem)
     }
 }
@@ -251,15 +317,15 @@ impl Encode for FieldInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::run_test;
     use crate::svd::{
         bitrange::BitRangeType, enumeratedvalue::EnumeratedValueBuilder,
         enumeratedvalues::EnumeratedValuesBuilder,
     };
+    use crate::{run_order_test, run_test};
 
     #[test]
     fn decode_encode() {
-        let tests = vec![
+        let tests = [
             (
                 FieldInfoBuilder::default()
                     .name("MODE".to_string())
@@ -270,6 +336,9 @@ mod tests {
                         range_type: BitRangeType::OffsetWidth,
                     })
                     .access(Some(Access::ReadWrite))
+                    .modified_write_values(Some(ModifiedWriteValues::OneToClear))
+                    .write_constraint(Some(WriteConstraint::UseEnumeratedValues(true)))
+                    .read_action(Some(ReadAction::Clear))
                     .enumerated_values(vec![EnumeratedValuesBuilder::default()
                         .values(vec![EnumeratedValueBuilder::default()
                             .name("WS0".to_string())
@@ -291,6 +360,11 @@ mod tests {
               <bitOffset>24</bitOffset>
               <bitWidth>2</bitWidth>
               <access>read-write</access>
+              <modifiedWriteValues>oneToClear</modifiedWriteValues>
+              <writeConstraint>
+                <useEnumeratedValues>true</useEnumeratedValues>
+              </writeConstraint>
+              <readAction>clear</readAction>
               <enumeratedValues>
                 <enumeratedValue>
                   <name>WS0</name>
@@ -323,5 +397,50 @@ mod tests {
         ];
 
         run_test::<FieldInfo>(&tests[..]);
+        run_order_test(&tests[..]);
+    }
+
+    #[test]
+    fn read_write_enumerated_values() {
+        let evs = |name: &str, usage: Option<Usage>| {
+            EnumeratedValuesBuilder::default()
+                .name(Some(name.to_string()))
+                .usage(usage)
+                .values(vec![EnumeratedValueBuilder::default()
+                    .name("V0".to_string())
+                    .value(Some(0))
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap()
+        };
+        let field = |enumerated_values| {
+            FieldInfoBuilder::default()
+                .name("MODE".to_string())
+                .bit_range(BitRange {
+                    offset: 0,
+                    width: 2,
+                    range_type: BitRangeType::OffsetWidth,
+                })
+                .enumerated_values(enumerated_values)
+                .build()
+                .unwrap()
+        };
+        let name = |ev: Option<&EnumeratedValues>| ev.and_then(|ev| ev.name.clone());
+
+        let f = field(vec![evs("BOTH", None)]);
+        assert_eq!(name(f.read_enumerated_values()), Some("BOTH".to_string()));
+        assert_eq!(name(f.write_enumerated_values()), Some("BOTH".to_string()));
+
+        let f = field(vec![
+            evs("W", Some(Usage::Write)),
+            evs("RW", Some(Usage::ReadWrite)),
+        ]);
+        assert_eq!(name(f.read_enumerated_values()), Some("RW".to_string()));
+        assert_eq!(name(f.write_enumerated_values()), Some("W".to_string()));
+
+        let f = field(vec![evs("R", Some(Usage::Read))]);
+        assert_eq!(name(f.read_enumerated_values()), Some("R".to_string()));
+        assert_eq!(name(f.write_enumerated_values()), None);
     }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::elementext::ElementExt;
 
@@ -40,10 +40,10 @@ impl Parse for Interrupt {
 
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "interrupt" {
-            return Err(SVDError::NotExpectedTag(tree.clone(), "interrupt".to_string()).into());
+            return Err(SVDError::NotExpectedTag(tree.into(), "interrupt".to_string()).into());
         }
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In interrupt `{}`", name))
+        Self::_parse(tree, name.clone()).in_element("interrupt", &name)
     }
 }
 
@@ -58,11 +58,10 @@ impl Encode for Interrupt {
             name: String::from("interrupt"),
             attributes: HashMap::new(),
             children: vec![
-                new_element("name", Some(self.name.clone())),
-                new_element("description", self.description.clone()),
-                new_element("value", Some(format!("{}", self.value))),
+                XMLNode::Element(new_element("name", Some(self.name.clone()))),
+                XMLNode::Element(new_element("description", self.description.clone())),
+                XMLNode::Element(new_element("value", Some(format!("{}", self.value)))),
             ],
-            text: None,
         })
     }
 }
@@ -74,7 +73,7 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
+        let tests = [(
             Interrupt {
                 name: String::from("test"),
                 description: Some(String::from("description")),
@@ -1,7 +1,7 @@
 use crate::elementext::ElementExt;
 
 use std::collections::HashMap;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::types::Parse;
 
@@ -28,7 +28,7 @@ impl Parse for ModifiedWriteValues {
 
     fn parse(tree: &Element) -> Result<Self> {
         use self::ModifiedWriteValues::*;
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         Ok(match text.as_ref() {
             "oneToClear" => OneToClear,
@@ -40,7 +40,7 @@ impl Parse for ModifiedWriteValues {
             "clear" => Clear,
             "set" => Set,
             "modify" => Modify,
-            s => return Err(SVDError::InvalidModifiedWriteValues(tree.clone(), s.into()).into()),
+            s => return Err(SVDError::InvalidModifiedWriteValues(tree.into(), s.into()).into()),
         })
     }
 }
@@ -68,8 +68,7 @@ impl Encode for ModifiedWriteValues {
             namespaces: None,
             name: String::from("modifiedWriteValues"),
             attributes: HashMap::new(),
-            children: vec![],
-            text: Some(v.into()),
+            children: vec![XMLNode::Text(v.into())],
         })
     }
 }
@@ -82,7 +81,7 @@ mod tests {
     #[test]
     fn decode_encode() {
         // FIXME: Do we need a more extensive test?
-        let tests = vec![(
+        let tests = [(
             ModifiedWriteValues::OneToToggle,
             "<modifiedWriteValues>oneToToggle</modifiedWriteValues>",
         )];
@@ -1,218 +1,98 @@
-use std::collections::HashMap;
+use core::ops::{Deref, DerefMut};
 
 use xmltree::Element;
 
-use crate::elementext::ElementExt;
-use crate::parse;
-
-use crate::encode::{Encode, EncodeChildren};
-
-use crate::new_element;
 use crate::types::Parse;
 
+use crate::elementext::ElementExt;
+use crate::encode::Encode;
 use crate::error::*;
+use crate::path::{ClusterPathIter, FieldPathIter, RegPathIter};
 use crate::svd::{
-    addressblock::AddressBlock,
-    interrupt::Interrupt,
-    register::{RegIter, RegIterMut},
+    dimelement::{expand_name, DimElement},
+    peripheralinfo::PeripheralInfo,
     registercluster::RegisterCluster,
-    registerproperties::RegisterProperties,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
-#[non_exhaustive]
-pub struct Peripheral {
-    /// The string identifies the peripheral. Peripheral names are required to be unique for a device
-    pub name: String,
-
-    /// Lowest address reserved or used by the peripheral
-    pub base_address: u64,
-
-    /// The string specifies the version of this peripheral description
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub version: Option<String>,
-
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub display_name: Option<String>,
-
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub group_name: Option<String>,
-
-    /// The string provides an overview of the purpose and functionality of the peripheral
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub description: Option<String>,
-
-    /// Specify an address range uniquely mapped to this peripheral
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub address_block: Option<AddressBlock>,
-
-    /// A peripheral can have multiple associated interrupts
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
-    pub interrupt: Vec<Interrupt>,
-
-    pub default_register_properties: RegisterProperties,
-
-    /// Group to enclose register definitions.
-    /// `None` indicates that the `<registers>` node is not present
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub registers: Option<Vec<RegisterCluster>>,
-
-    /// Specify the peripheral name from which to inherit data. Elements specified subsequently override inherited values
-    #[cfg_attr(feature = "serde", serde(default))]
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
-    pub derived_from: Option<String>,
+pub enum Peripheral {
+    Single(PeripheralInfo),
+    Array(PeripheralInfo, DimElement),
 }
 
-#[derive(Clone, Debug, Default, PartialEq)]
-pub struct PeripheralBuilder {
-    name: Option<String>,
-    base_address: Option<u64>,
-    version: Option<String>,
-    display_name: Option<String>,
-    group_name: Option<String>,
-    description: Option<String>,
-    address_block: Option<AddressBlock>,
-    interrupt: Vec<Interrupt>,
-    default_register_properties: RegisterProperties,
-    registers: Option<Vec<RegisterCluster>>,
-    derived_from: Option<String>,
-}
+impl Deref for Peripheral {
+    type Target = PeripheralInfo;
 
-impl From<Peripheral> for PeripheralBuilder {
-    fn from(p: Peripheral) -> Self {
-        Self {
-            name: Some(p.name),
-            base_address: Some(p.base_address),
-            version: p.version,
-            display_name: p.display_name,
-            group_name: p.group_name,
-            description: p.description,
-            address_block: p.address_block,
-            interrupt: p.interrupt,
-            default_register_properties: p.default_register_properties,
-            registers: p.registers,
-            derived_from: p.derived_from,
+    fn deref(&self) -> &PeripheralInfo {
+        match self {
+            Peripheral::Single(info) => info,
+            Peripheral::Array(info, _) => info,
         }
     }
 }
 
-impl PeripheralBuilder {
-    pub fn name(mut self, value: String) -> Self {
-        self.name = Some(value);
-        self
-    }
-    pub fn base_address(mut self, value: u64) -> Self {
-        self.base_address = Some(value);
-        self
-    }
-    pub fn version(mut self, value: Option<String>) -> Self {
-        self.version = value;
-        self
-    }
-    pub fn display_name(mut self, value: Option<String>) -> Self {
-        self.display_name = value;
-        self
-    }
-    pub fn group_name(mut self, value: Option<String>) -> Self {
-        self.group_name = value;
-        self
-    }
-    pub fn description(mut self, value: Option<String>) -> Self {
-        self.description = value;
-        self
-    }
-    pub fn address_block(mut self, value: Option<AddressBlock>) -> Self {
-        self.address_block = value;
-        self
-    }
-    pub fn interrupt(mut self, value: Vec<Interrupt>) -> Self {
-        self.interrupt = value;
-        self
-    }
-    pub fn default_register_properties(mut self, value: RegisterProperties) -> Self {
-        self.default_register_properties = value;
-        self
-    }
-    pub fn registers(mut self, value: Option<Vec<RegisterCluster>>) -> Self {
-        self.registers = value;
-        self
-    }
-    pub fn derived_from(mut self, value: Option<String>) -> Self {
-        self.derived_from = value;
-        self
-    }
-    pub fn build(self) -> Result<Peripheral> {
-        (Peripheral {
-            name: self
-                .name
-                .ok_or_else(|| BuildError::Uninitialized("name".to_string()))?,
-            base_address: self
-                .base_address
-                .ok_or_else(|| BuildError::Uninitialized("base_address".to_string()))?,
-            version: self.version,
-            display_name: self.display_name,
-            group_name: self.group_name,
-            description: self.description,
-            address_block: self.address_block,
-            interrupt: self.interrupt,
-            default_register_properties: self.default_register_properties,
-            registers: self.registers,
-            derived_from: self.derived_from,
-        })
-        .validate()
+impl DerefMut for Peripheral {
+    fn deref_mut(&mut self) -> &mut PeripheralInfo {
+        match self {
+            Peripheral::Single(info) => info,
+            Peripheral::Array(info, _) => info,
+        }
     }
 }
 
 impl Peripheral {
-    #[allow(clippy::unnecessary_wraps)]
-    fn validate(self) -> Result<Self> {
-        // TODO
-        #[cfg(feature = "strict")]
-        check_dimable_name(&self.name, "name")?;
-        if let Some(_name) = self.derived_from.as_ref() {
-            #[cfg(feature = "strict")]
-            check_dimable_name(_name, "derivedFrom")?;
-        } else if let Some(registers) = self.registers.as_ref() {
-            if registers.is_empty() {
-                #[cfg(feature = "strict")]
-                return Err(SVDError::EmptyRegisters)?;
-            }
+    /// Returns one `Single` peripheral per element of the array
+    /// with its base address.
+    /// A `Single` peripheral is returned as is.
+    /// Expansion stops at the first element whose base address overflows
+    pub fn expand(&self) -> Vec<Peripheral> {
+        match self {
+            Peripheral::Single(_) => vec![self.clone()],
+            Peripheral::Array(info, array_info) => array_info
+                .indexes()
+                .enumerate()
+                .map_while(|(i, index)| {
+                    let increment = u64::from(array_info.dim_increment).checked_mul(i as u64)?;
+                    let base_address = info.base_address.checked_add(increment)?;
+                    let mut info = info.clone();
+                    info.name = expand_name(&info.name, &index);
+                    info.display_name = info.display_name.map(|n| expand_name(&n, &index));
+                    info.base_address = base_address;
+                    Some(Peripheral::Single(info))
+                })
+                .collect(),
         }
-        Ok(self)
     }
 
     /// returns iterator over all registers peripheral contains
-    pub fn reg_iter(&self) -> RegIter {
-        if let Some(regs) = &self.registers {
-            let mut rem: Vec<&RegisterCluster> = Vec::with_capacity(regs.len());
-            for r in regs.iter().rev() {
-                rem.push(r);
-            }
-            RegIter { rem }
-        } else {
-            RegIter { rem: Vec::new() }
-        }
-    }
-
-    /// returns mutable iterator over all registers peripheral contains
-    pub fn reg_iter_mut(&mut self) -> RegIterMut {
-        if let Some(regs) = &mut self.registers {
-            let mut rem: Vec<&mut RegisterCluster> = Vec::with_capacity(regs.len());
-            for r in regs.iter_mut().rev() {
-                rem.push(r);
-            }
-            RegIterMut { rem }
-        } else {
-            RegIterMut { rem: Vec::new() }
-        }
+    /// together with enclosing clusters, offsets and inherited properties
+    pub fn reg_path_iter(&self) -> RegPathIter<'_> {
+        RegPathIter::new(
+            Some(self),
+            self.registers.as_deref().unwrap_or(&[]),
+            self.default_register_properties,
+        )
+    }
+
+    /// returns iterator over all clusters peripheral contains
+    /// together with enclosing clusters, offsets and inherited properties
+    pub fn cluster_path_iter(&self) -> ClusterPathIter<'_> {
+        ClusterPathIter::new(
+            Some(self),
+            self.registers.as_deref().unwrap_or(&[]),
+            self.default_register_properties,
+        )
+    }
+
+    /// returns iterator over all fields peripheral contains
+    /// together with enclosing registers and clusters
+    pub fn field_path_iter(&self) -> FieldPathIter<'_> {
+        FieldPathIter::new(
+            Some(self),
+            self.registers.as_deref().unwrap_or(&[]),
+            self.default_register_properties,
+        )
     }
 }
 
@@ -222,48 +102,45 @@ impl Parse for Peripheral {
 
     fn parse(tree: &Element) -> Result<Self> {
         if tree.name != "peripheral" {
-            return Err(SVDError::NotExpectedTag(tree.clone(), "peripheral".to_string()).into());
+            return Err(SVDError::NotExpectedTag(tree.into(), "peripheral".to_string()).into());
         }
-        let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In peripheral `{}`", name))
+
+        Self::_parse(tree, PeripheralInfo::parse(tree)?)
     }
 }
 
 impl Peripheral {
-    fn _parse(tree: &Element, name: String) -> Result<Self> {
-        PeripheralBuilder::default()
-            .name(name)
-            .version(tree.get_child_text_opt("version")?)
-            .display_name(tree.get_child_text_opt("displayName")?)
-            .group_name(tree.get_child_text_opt("groupName")?)
-            .description(tree.get_child_text_opt("description")?)
-            .base_address(tree.get_child_u64("baseAddress")?)
-            .address_block(parse::optional::<AddressBlock>("addressBlock", tree)?)
-            .interrupt({
-                let interrupt: Result<Vec<_>, _> = tree
-                    .children
-                    .iter()
-                    .filter(|t| t.name == "interrupt")
-                    .enumerate()
-                    .map(|(e, i)| {
-                        Interrupt::parse(i).with_context(|| format!("Parsing interrupt #{}", e))
-                    })
-                    .collect();
-                interrupt?
-            })
-            .default_register_properties(RegisterProperties::parse(tree)?)
-            .registers(if let Some(registers) = tree.get_child("registers") {
-                let rs: Result<Vec<_>, _> = registers
-                    .children
-                    .iter()
-                    .map(RegisterCluster::parse)
-                    .collect();
-                Some(rs?)
-            } else {
-                None
-            })
-            .derived_from(tree.attributes.get("derivedFrom").map(|s| s.to_owned()))
-            .build()
+    /// Parses a peripheral whose registers and clusters have already been parsed
+    pub(crate) fn parse_with(
+        tree: &Element,
+        registers: Option<Vec<RegisterCluster>>,
+    ) -> Result<Self> {
+        if tree.name != "peripheral" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "peripheral".to_string()).into());
+        }
+        Self::_parse(tree, PeripheralInfo::parse_with(tree, registers)?)
+    }
+
+    fn _parse(tree: &Element, info: PeripheralInfo) -> Result<Self> {
+        if tree.get_child("dimIncrement").is_some() {
+            let array_info = DimElement::parse(tree)?;
+            check_has_placeholder(&info.name, "peripheral")?;
+
+            if let Some(indices) = &array_info.dim_index {
+                if array_info.dim as usize != indices.len() {
+                    return Err(SVDError::DimIndexLength(
+                        tree.into(),
+                        array_info.dim,
+                        indices.len(),
+                    )
+                    .into());
+                }
+            }
+
+            Ok(Peripheral::Array(info, array_info))
+        } else {
+            Ok(Peripheral::Single(info))
+        }
     }
 }
 
@@ -271,69 +148,108 @@ impl Encode for Peripheral {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
-        let mut elem = Element {
-            prefix: None,
-            namespace: None,
-            namespaces: None,
-            name: String::from("peripheral"),
-            attributes: HashMap::new(),
-            children: vec![new_element("name", Some(self.name.clone()))],
-            text: None,
-        };
-
-        if let Some(v) = &self.version {
-            elem.children
-                .push(new_element("version", Some(v.to_string())));
-        };
-        if let Some(v) = &self.display_name {
-            elem.children
-                .push(new_element("displayName", Some(v.to_string())));
-        };
-        if let Some(v) = &self.group_name {
-            elem.children
-                .push(new_element("groupName", Some(v.to_string())));
-        };
-        if let Some(v) = &self.description {
-            elem.children
-                .push(new_element("description", Some(v.to_string())));
-        };
-        elem.children.push(new_element(
-            "baseAddress",
-            Some(format!("0x{:.08x}", self.base_address)),
-        ));
-
-        elem.children
-            .extend(self.default_register_properties.encode()?);
-
-        if let Some(v) = &self.address_block {
-            elem.children.push(v.encode()?);
-        };
-
-        let interrupts: Result<Vec<_>, _> = self.interrupt.iter().map(Interrupt::encode).collect();
-
-        elem.children.append(&mut interrupts?);
-
-        if let Some(v) = &self.registers {
-            let children: Result<Vec<_>, _> = v.iter().map(|e| e.encode()).collect();
-
-            elem.children.push(Element {
-                prefix: None,
-                namespace: None,
-                namespaces: None,
-                name: String::from("registers"),
-                attributes: HashMap::new(),
-                children: children?,
-                text: None,
-            });
-        };
-
-        if let Some(v) = &self.derived_from {
-            elem.attributes
-                .insert(String::from("derivedFrom"), v.to_string());
+        match self {
+            Peripheral::Single(info) => info.encode(),
+            Peripheral::Array(info, array_info) => {
+                let mut base = info.encode()?;
+                base.merge(&array_info.encode()?);
+                Ok(base)
+            }
         }
-
-        Ok(elem)
     }
 }
 
-// TODO: add Peripheral encode / decode tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimelement::DimElementBuilder;
+    use crate::peripheralinfo::PeripheralInfoBuilder;
+
+    use crate::run_test;
+    use crate::types::DimIndex;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (
+                Peripheral::Single(
+                    PeripheralInfoBuilder::default()
+                        .name("TIMER0".to_string())
+                        .alternate_peripheral(Some("TIMER".to_string()))
+                        .prepend_to_name(Some("T0_".to_string()))
+                        .append_to_name(Some("_0".to_string()))
+                        .header_struct_name(Some("TIMER_Type".to_string()))
+                        .disable_condition(Some("TIMER0->CR == 0".to_string()))
+                        .base_address(0x40000000)
+                        .build()
+                        .unwrap(),
+                ),
+                "
+                <peripheral>
+                  <name>TIMER0</name>
+                  <alternatePeripheral>TIMER</alternatePeripheral>
+                  <prependToName>T0_</prependToName>
+                  <appendToName>_0</appendToName>
+                  <headerStructName>TIMER_Type</headerStructName>
+                  <disableCondition>TIMER0->CR == 0</disableCondition>
+                  <baseAddress>0x40000000</baseAddress>
+                </peripheral>
+                ",
+            ),
+            (
+                Peripheral::Array(
+                    PeripheralInfoBuilder::default()
+                        .name("UART%s".to_string())
+                        .base_address(0x40010000)
+                        .build()
+                        .unwrap(),
+                    DimElementBuilder::default()
+                        .dim(2)
+                        .dim_increment(0x1000)
+                        .dim_index(Some(DimIndex::List(vec!["A".to_string(), "B".to_string()])))
+                        .build()
+                        .unwrap(),
+                ),
+                "
+                <peripheral>
+                  <name>UART%s</name>
+                  <baseAddress>0x40010000</baseAddress>
+                  <dim>2</dim>
+                  <dimIncrement>4096</dimIncrement>
+                  <dimIndex>A,B</dimIndex>
+                </peripheral>
+                ",
+            ),
+        ];
+        run_test::<Peripheral>(&tests[..]);
+    }
+
+    #[test]
+    fn expand() {
+        let peripheral = Peripheral::Array(
+            PeripheralInfoBuilder::default()
+                .name("UART[%s]".to_string())
+                .display_name(Some("UART%s".to_string()))
+                .base_address(0x40010000)
+                .build()
+                .unwrap(),
+            DimElementBuilder::default()
+                .dim(3)
+                .dim_increment(0x400)
+                .build()
+                .unwrap(),
+        );
+        let peripherals = peripheral.expand();
+        assert_eq!(peripherals.len(), 3);
+        for (i, p) in peripherals.iter().enumerate() {
+            match p {
+                Peripheral::Single(info) => {
+                    assert_eq!(info.name, format!("UART{}", i));
+                    assert_eq!(info.display_name, Some(format!("UART{}", i)));
+                    assert_eq!(info.base_address, 0x40010000 + 0x400 * i as u64);
+                }
+                Peripheral::Array(..) => panic!("expected single peripheral"),
+            }
+        }
+    }
+}
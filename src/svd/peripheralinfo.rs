@@ -0,0 +1,957 @@
+use std::collections::HashMap;
+
+use xmltree::{Element, XMLNode};
+
+use crate::elementext::ElementExt;
+use crate::encode::{Encode, EncodeChildren};
+
+use crate::new_element;
+use crate::parse;
+use crate::types::Parse;
+
+use crate::error::*;
+use crate::svd::{
+    addressblock::AddressBlock,
+    interrupt::Interrupt,
+    register::{RegIter, RegIterMut},
+    registercluster::RegisterCluster,
+    registerproperties::RegisterProperties,
+    vendorextensions::VendorExtensions,
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct PeripheralInfo {
+    /// The string identifies the peripheral. Peripheral names are required to be unique for a device
+    pub name: String,
+
+    /// Lowest address reserved or used by the peripheral
+    pub base_address: u64,
+
+    /// The string specifies the version of this peripheral description
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub version: Option<String>,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub display_name: Option<String>,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub group_name: Option<String>,
+
+    /// The string provides an overview of the purpose and functionality of the peripheral
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub description: Option<String>,
+
+    /// Specify the name of the original peripheral if this peripheral is an alternate
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub alternate_peripheral: Option<String>,
+
+    /// Prefix prepended to the names of all registers of the peripheral in the device header file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub prepend_to_name: Option<String>,
+
+    /// Suffix appended to the names of all registers of the peripheral in the device header file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub append_to_name: Option<String>,
+
+    /// Specify the struct type name created in the device header file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub header_struct_name: Option<String>,
+
+    /// C-language compliant logical expression returning a TRUE or FALSE result.
+    /// If TRUE, the refreshing of the display for this peripheral is disabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub disable_condition: Option<String>,
+
+    /// Specify address ranges uniquely mapped to this peripheral
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub address_block: Vec<AddressBlock>,
+
+    /// A peripheral can have multiple associated interrupts
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub interrupt: Vec<Interrupt>,
+
+    pub default_register_properties: RegisterProperties,
+
+    /// Group to enclose register definitions.
+    /// `None` indicates that the `<registers>` node is not present
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub registers: Option<Vec<RegisterCluster>>,
+
+    /// Specify the peripheral name from which to inherit data. Elements specified subsequently override inherited values
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub derived_from: Option<String>,
+
+    /// Vendor specific extensions of the peripheral, kept as raw XML
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_extensions: Option<VendorExtensions>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PeripheralError {
+    #[error("Register `{0}` at offset 0x{1:x} is outside of all `registers` address blocks")]
+    RegisterOutsideAddressBlocks(String, u64),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeripheralInfoBuilder {
+    name: Option<String>,
+    base_address: Option<u64>,
+    version: Option<String>,
+    display_name: Option<String>,
+    group_name: Option<String>,
+    description: Option<String>,
+    alternate_peripheral: Option<String>,
+    prepend_to_name: Option<String>,
+    append_to_name: Option<String>,
+    header_struct_name: Option<String>,
+    disable_condition: Option<String>,
+    address_block: Vec<AddressBlock>,
+    interrupt: Vec<Interrupt>,
+    default_register_properties: RegisterProperties,
+    registers: Option<Vec<RegisterCluster>>,
+    derived_from: Option<String>,
+    vendor_extensions: Option<VendorExtensions>,
+}
+
+impl From<PeripheralInfo> for PeripheralInfoBuilder {
+    fn from(p: PeripheralInfo) -> Self {
+        Self {
+            name: Some(p.name),
+            base_address: Some(p.base_address),
+            version: p.version,
+            display_name: p.display_name,
+            group_name: p.group_name,
+            description: p.description,
+            alternate_peripheral: p.alternate_peripheral,
+            prepend_to_name: p.prepend_to_name,
+            append_to_name: p.append_to_name,
+            header_struct_name: p.header_struct_name,
+            disable_condition: p.disable_condition,
+            address_block: p.address_block,
+            interrupt: p.interrupt,
+            default_register_properties: p.default_register_properties,
+            registers: p.registers,
+            derived_from: p.derived_from,
+            vendor_extensions: p.vendor_extensions,
+        }
+    }
+}
+
+impl PeripheralInfoBuilder {
+    pub fn name(mut self, value: String) -> Self {
+        self.name = Some(value);
+        self
+    }
+    pub fn base_address(mut self, value: u64) -> Self {
+        self.base_address = Some(value);
+        self
+    }
+    pub fn version(mut self, value: Option<String>) -> Self {
+        self.version = value;
+        self
+    }
+    pub fn display_name(mut self, value: Option<String>) -> Self {
+        self.display_name = value;
+        self
+    }
+    pub fn group_name(mut self, value: Option<String>) -> Self {
+        self.group_name = value;
+        self
+    }
+    pub fn description(mut self, value: Option<String>) -> Self {
+        self.description = value;
+        self
+    }
+    pub fn alternate_peripheral(mut self, value: Option<String>) -> Self {
+        self.alternate_peripheral = value;
+        self
+    }
+    pub fn prepend_to_name(mut self, value: Option<String>) -> Self {
+        self.prepend_to_name = value;
+        self
+    }
+    pub fn append_to_name(mut self, value: Option<String>) -> Self {
+        self.append_to_name = value;
+        self
+    }
+    pub fn header_struct_name(mut self, value: Option<String>) -> Self {
+        self.header_struct_name = value;
+        self
+    }
+    pub fn disable_condition(mut self, value: Option<String>) -> Self {
+        self.disable_condition = value;
+        self
+    }
+    pub fn address_block(mut self, value: Vec<AddressBlock>) -> Self {
+        self.address_block = value;
+        self
+    }
+    pub fn interrupt(mut self, value: Vec<Interrupt>) -> Self {
+        self.interrupt = value;
+        self
+    }
+    pub fn default_register_properties(mut self, value: RegisterProperties) -> Self {
+        self.default_register_properties = value;
+        self
+    }
+    pub fn registers(mut self, value: Option<Vec<RegisterCluster>>) -> Self {
+        self.registers = value;
+        self
+    }
+    pub fn derived_from(mut self, value: Option<String>) -> Self {
+        self.derived_from = value;
+        self
+    }
+    pub fn vendor_extensions(mut self, value: Option<VendorExtensions>) -> Self {
+        self.vendor_extensions = value;
+        self
+    }
+    pub fn build(self) -> Result<PeripheralInfo, Error> {
+        (PeripheralInfo {
+            name: self
+                .name
+                .ok_or_else(|| BuildError::Uninitialized("name".to_string()))?,
+            base_address: self
+                .base_address
+                .ok_or_else(|| BuildError::Uninitialized("base_address".to_string()))?,
+            version: self.version,
+            display_name: self.display_name,
+            group_name: self.group_name,
+            description: self.description,
+            alternate_peripheral: self.alternate_peripheral,
+            prepend_to_name: self.prepend_to_name,
+            append_to_name: self.append_to_name,
+            header_struct_name: self.header_struct_name,
+            disable_condition: self.disable_condition,
+            address_block: self.address_block,
+            interrupt: self.interrupt,
+            default_register_properties: self.default_register_properties,
+            registers: self.registers,
+            derived_from: self.derived_from,
+            vendor_extensions: self.vendor_extensions,
+        })
+        .validate()
+        .map_err(Error::from)
+    }
+}
+
+impl PeripheralInfo {
+    #[allow(clippy::unnecessary_wraps)]
+    fn validate(self) -> Result<Self> {
+        // TODO
+        #[cfg(feature = "strict")]
+        check_dimable_name(&self.name, "name")?;
+        #[cfg(feature = "strict")]
+        {
+            if let Some(name) = self.alternate_peripheral.as_ref() {
+                check_dimable_name(name, "alternatePeripheral")?;
+            }
+            if let Some(name) = self.header_struct_name.as_ref() {
+                check_name(name, "headerStructName")?;
+            }
+        }
+        if let Some(_name) = self.derived_from.as_ref() {
+            #[cfg(feature = "strict")]
+            check_dimable_name(_name, "derivedFrom")?;
+        } else if let Some(registers) = self.registers.as_ref() {
+            if registers.is_empty() {
+                #[cfg(feature = "strict")]
+                return Err(SVDError::EmptyRegisters)?;
+            }
+        }
+        #[cfg(feature = "strict")]
+        {
+            if let Some(registers) = self.registers.as_ref() {
+                crate::svd::registercluster::check_alternates(
+                    registers,
+                    self.default_register_properties.size,
+                )?;
+            }
+            self.check_address_blocks(self.default_register_properties)?;
+        }
+        Ok(self)
+    }
+
+    /// Checks that every register lies inside an address block used for registers.
+    /// Registers inherit their size from `defaults`, registers of unknown size take one byte.
+    /// Nothing is checked when the peripheral has no address blocks.
+    /// Arrays are checked without expanding them
+    #[cfg(feature = "strict")]
+    pub(crate) fn check_address_blocks(&self, defaults: RegisterProperties) -> Result<()> {
+        use crate::path::RegPathIter;
+        use crate::svd::addressblock::AddressBlockUsage;
+        use crate::svd::{Cluster, Register};
+        use core::convert::TryFrom;
+
+        let registers = match &self.registers {
+            Some(registers) if !self.address_block.is_empty() => registers,
+            _ => return Ok(()),
+        };
+        let blocks: Vec<_> = self
+            .address_block
+            .iter()
+            .filter(|b| b.usage == AddressBlockUsage::Registers)
+            .map(|b| {
+                (
+                    u128::from(b.offset),
+                    u128::from(b.offset) + u128::from(b.size),
+                )
+            })
+            .collect();
+        for r in RegPathIter::new(None, registers, defaults) {
+            let bytes = r
+                .properties
+                .size
+                .map_or(1, |size| size / 8 + u32::from(size % 8 != 0));
+            // (stride, count) of the enclosing cluster arrays and of the register array
+            let arrays: Vec<_> = r
+                .clusters
+                .iter()
+                .filter_map(|c| match c {
+                    Cluster::Single(_) => None,
+                    Cluster::Array(_, dim) => Some(dim),
+                })
+                .chain(match r.register {
+                    Register::Single(_) => None,
+                    Register::Array(_, dim) => Some(dim),
+                })
+                .map(|dim| (u128::from(dim.dim_increment), u128::from(dim.dim)))
+                .collect();
+            if arrays.iter().any(|&(_, count)| count == 0) {
+                continue;
+            }
+            if let Some(start) =
+                first_outside(u128::from(r.offset), &arrays, u128::from(bytes), &blocks)
+            {
+                let start = u64::try_from(start).unwrap_or(u64::MAX);
+                return Err(
+                    PeripheralError::RegisterOutsideAddressBlocks(r.path_name(), start).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// returns iterator over all registers peripheral contains
+    pub fn reg_iter(&self) -> RegIter<'_> {
+        if let Some(regs) = &self.registers {
+            let mut rem: Vec<&RegisterCluster> = Vec::with_capacity(regs.len());
+            for r in regs.iter().rev() {
+                rem.push(r);
+            }
+            RegIter { rem }
+        } else {
+            RegIter { rem: Vec::new() }
+        }
+    }
+
+    /// returns mutable iterator over all registers peripheral contains
+    pub fn reg_iter_mut(&mut self) -> RegIterMut<'_> {
+        if let Some(regs) = &mut self.registers {
+            let mut rem: Vec<&mut RegisterCluster> = Vec::with_capacity(regs.len());
+            for r in regs.iter_mut().rev() {
+                rem.push(r);
+            }
+            RegIterMut { rem }
+        } else {
+            RegIterMut { rem: Vec::new() }
+        }
+    }
+}
+
+/// Returns the offset of the first register instance which is not inside one of the `blocks`.
+///
+/// The instances start at `offset` plus a multiple of the stride of each of the `arrays`,
+/// outermost first. The array is inside when its first and last element are inside
+/// the same block. Otherwise the elements inside each block are skipped over by computing
+/// the last one of them, so only elements which are not inside a single block are visited
+#[cfg(feature = "strict")]
+fn first_outside(
+    offset: u128,
+    arrays: &[(u128, u128)],
+    bytes: u128,
+    blocks: &[(u128, u128)],
+) -> Option<u128> {
+    let block_of = |start: u128, end: u128| {
+        blocks
+            .iter()
+            .find(|&&(b_start, b_end)| start >= b_start && end <= b_end)
+    };
+    let ((stride, count), inner) = match arrays.split_first() {
+        Some((&array, inner)) => (array, inner),
+        None => return block_of(offset, offset + bytes).map_or(Some(offset), |_| None),
+    };
+    // Extent of the instances of one element of this array
+    let extent = bytes
+        + inner
+            .iter()
+            .map(|&(stride, count)| stride * (count - 1))
+            .sum::<u128>();
+    if block_of(offset, offset + stride * (count - 1) + extent).is_some() {
+        return None;
+    }
+    let mut i = 0;
+    while i < count {
+        let start = offset + i * stride;
+        if let Some(&(_, b_end)) = block_of(start, start + extent) {
+            if stride == 0 {
+                return None;
+            }
+            // Elements up to the last one ending inside this block are inside as well
+            i = (b_end - extent - offset) / stride + 1;
+        } else {
+            if let Some(start) = first_outside(start, inner, bytes, blocks) {
+                return Some(start);
+            }
+            if stride == 0 {
+                return None;
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+impl Parse for PeripheralInfo {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        let name = tree.get_child_text("name")?;
+        Self::_parse(tree, name.clone(), || Self::parse_registers(tree))
+            .in_element("peripheral", &name)
+    }
+}
+
+impl PeripheralInfo {
+    /// Parses a peripheral whose registers and clusters have already been parsed
+    pub(crate) fn parse_with(
+        tree: &Element,
+        registers: Option<Vec<RegisterCluster>>,
+    ) -> Result<Self> {
+        let name = tree.get_child_text("name")?;
+        Self::_parse(tree, name.clone(), || Ok(registers)).in_element("peripheral", &name)
+    }
+
+    fn parse_registers(tree: &Element) -> Result<Option<Vec<RegisterCluster>>> {
+        if let Some(registers) = tree.get_child("registers") {
+            let rs: Result<Vec<_>, _> = registers
+                .children
+                .iter()
+                .filter_map(XMLNode::as_element)
+                .map(RegisterCluster::parse)
+                .collect();
+            Some(rs).transpose()
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn _parse<F>(tree: &Element, name: String, registers: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<Option<Vec<RegisterCluster>>>,
+    {
+        PeripheralInfoBuilder::default()
+            .name(name)
+            .version(tree.get_child_text_opt("version")?)
+            .display_name(tree.get_child_text_opt("displayName")?)
+            .group_name(tree.get_child_text_opt("groupName")?)
+            .description(tree.get_child_text_opt("description")?)
+            .alternate_peripheral(tree.get_child_text_opt("alternatePeripheral")?)
+            .prepend_to_name(tree.get_child_text_opt("prependToName")?)
+            .append_to_name(tree.get_child_text_opt("appendToName")?)
+            .header_struct_name(tree.get_child_text_opt("headerStructName")?)
+            .disable_condition(tree.get_child_text_opt("disableCondition")?)
+            .base_address(tree.get_child_u64("baseAddress")?)
+            .address_block({
+                let address_block: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter_map(XMLNode::as_element)
+                    .filter(|t| t.name == "addressBlock")
+                    .enumerate()
+                    .map(|(e, b)| {
+                        AddressBlock::parse(b)
+                            .with_context(|| format!("Parsing address block #{}", e))
+                    })
+                    .collect();
+                address_block?
+            })
+            .interrupt({
+                let interrupt: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter_map(XMLNode::as_element)
+                    .filter(|t| t.name == "interrupt")
+                    .enumerate()
+                    .map(|(e, i)| {
+                        Interrupt::parse(i).with_context(|| format!("Parsing interrupt #{}", e))
+                    })
+                    .collect();
+                interrupt?
+            })
+            .default_register_properties(RegisterProperties::parse(tree)?)
+            .registers(registers()?)
+            .derived_from(tree.attributes.get("derivedFrom").map(|s| s.to_owned()))
+            .vendor_extensions(parse::optional::<VendorExtensions>(
+                "vendorExtensions",
+                tree,
+            )?)
+            .build()
+            .map_err(Error::into_anyhow)
+    }
+}
+
+impl Encode for PeripheralInfo {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut elem = Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("peripheral"),
+            attributes: HashMap::new(),
+            children: vec![XMLNode::Element(new_element(
+                "name",
+                Some(self.name.clone()),
+            ))],
+        };
+
+        if let Some(v) = &self.version {
+            elem.children.push(XMLNode::Element(new_element(
+                "version",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.display_name {
+            elem.children.push(XMLNode::Element(new_element(
+                "displayName",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.description {
+            elem.children.push(XMLNode::Element(new_element(
+                "description",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.alternate_peripheral {
+            elem.children.push(XMLNode::Element(new_element(
+                "alternatePeripheral",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.group_name {
+            elem.children.push(XMLNode::Element(new_element(
+                "groupName",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.prepend_to_name {
+            elem.children.push(XMLNode::Element(new_element(
+                "prependToName",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.append_to_name {
+            elem.children.push(XMLNode::Element(new_element(
+                "appendToName",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.header_struct_name {
+            elem.children.push(XMLNode::Element(new_element(
+                "headerStructName",
+                Some(v.to_string()),
+            )));
+        };
+        if let Some(v) = &self.disable_condition {
+            elem.children.push(XMLNode::Element(new_element(
+                "disableCondition",
+                Some(v.to_string()),
+            )));
+        };
+        elem.children.push(XMLNode::Element(new_element(
+            "baseAddress",
+            Some(format!("0x{:.08x}", self.base_address)),
+        )));
+
+        elem.children.extend(
+            self.default_register_properties
+                .encode()?
+                .into_iter()
+                .map(XMLNode::Element),
+        );
+
+        let address_blocks: Result<Vec<_>, _> = self
+            .address_block
+            .iter()
+            .map(AddressBlock::encode)
+            .collect();
+
+        elem.children
+            .extend(address_blocks?.into_iter().map(XMLNode::Element));
+
+        let interrupts: Result<Vec<_>, _> = self.interrupt.iter().map(Interrupt::encode).collect();
+
+        elem.children
+            .extend(interrupts?.into_iter().map(XMLNode::Element));
+
+        if let Some(v) = &self.registers {
+            let children: Result<Vec<_>, _> = v.iter().map(|e| e.encode()).collect();
+
+            elem.children.push(XMLNode::Element(Element {
+                prefix: None,
+                namespace: None,
+                namespaces: None,
+                name: String::from("registers"),
+                attributes: HashMap::new(),
+                children: children?.into_iter().map(XMLNode::Element).collect(),
+            }));
+        };
+
+        if let Some(v) = &self.derived_from {
+            elem.attributes
+                .insert(String::from("derivedFrom"), v.to_string());
+        }
+
+        if let Some(v) = &self.vendor_extensions {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        }
+
+        Ok(elem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "strict")]
+    #[test]
+    fn registers_inside_address_blocks() {
+        use super::*;
+
+        let peripheral = |offset: &str| {
+            let xml = format!(
+                "
+                <peripheral>
+                    <name>TIMER0</name>
+                    <baseAddress>0x40000000</baseAddress>
+                    <size>32</size>
+                    <addressBlock>
+                        <offset>0x0</offset>
+                        <size>0x8</size>
+                        <usage>registers</usage>
+                    </addressBlock>
+                    <addressBlock>
+                        <offset>0x8</offset>
+                        <size>0x8</size>
+                        <usage>buffer</usage>
+                    </addressBlock>
+                    <registers>
+                        <register>
+                            <name>CR</name>
+                            <addressOffset>{}</addressOffset>
+                        </register>
+                    </registers>
+                </peripheral>
+                ",
+                offset
+            );
+            PeripheralInfo::parse(&Element::parse(xml.as_bytes()).unwrap())
+        };
+
+        peripheral("0x4").unwrap();
+        peripheral("0x6").expect_err("register crosses the end of the block");
+        peripheral("0x8").expect_err("register is inside a buffer block");
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    fn arrays_inside_address_blocks() {
+        use super::*;
+
+        let peripheral = |registers: &str| {
+            let xml = format!(
+                "
+                <peripheral>
+                    <name>TIMER0</name>
+                    <baseAddress>0x40000000</baseAddress>
+                    <size>32</size>
+                    <addressBlock>
+                        <offset>0x0</offset>
+                        <size>0x8</size>
+                        <usage>registers</usage>
+                    </addressBlock>
+                    <addressBlock>
+                        <offset>0x8</offset>
+                        <size>0x8</size>
+                        <usage>buffer</usage>
+                    </addressBlock>
+                    <addressBlock>
+                        <offset>0x10</offset>
+                        <size>0x10</size>
+                        <usage>registers</usage>
+                    </addressBlock>
+                    <registers>{}</registers>
+                </peripheral>
+                ",
+                registers
+            );
+            PeripheralInfo::parse(&Element::parse(xml.as_bytes()).unwrap())
+        };
+        let array = |offset: &str, dim: &str| {
+            peripheral(&format!(
+                "
+                <register>
+                    <dim>{}</dim>
+                    <dimIncrement>0x4</dimIncrement>
+                    <name>DATA%s</name>
+                    <addressOffset>{}</addressOffset>
+                </register>
+                ",
+                dim, offset
+            ))
+        };
+        let outside = |result: Result<PeripheralInfo>| match result
+            .unwrap_err()
+            .downcast_ref::<PeripheralError>()
+        {
+            Some(PeripheralError::RegisterOutsideAddressBlocks(_, offset)) => *offset,
+            err => panic!("unexpected {:?}", err),
+        };
+
+        array("0x0", "2").unwrap();
+        array("0x10", "4").unwrap();
+        assert_eq!(outside(array("0x0", "3")), 0x8);
+        assert_eq!(outside(array("0x10", "4294967295")), 0x20);
+        peripheral(
+            "
+            <cluster>
+                <dim>2</dim>
+                <dimIncrement>0x10</dimIncrement>
+                <name>CH%s</name>
+                <addressOffset>0x0</addressOffset>
+                <register>
+                    <dim>2</dim>
+                    <dimIncrement>0x4</dimIncrement>
+                    <name>DATA%s</name>
+                    <addressOffset>0x0</addressOffset>
+                </register>
+            </cluster>
+            ",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn alternates() {
+        use super::*;
+
+        let peripheral = |registers: &str| {
+            let xml = format!(
+                "
+                <peripheral>
+                    <name>TIMER0</name>
+                    <baseAddress>0x40000000</baseAddress>
+                    <size>32</size>
+                    <registers>
+                        <register>
+                            <name>CCMR1_Output</name>
+                            <addressOffset>0x18</addressOffset>
+                        </register>
+                        <cluster>
+                            <name>MODE0</name>
+                            <addressOffset>0x20</addressOffset>
+                            <register>
+                                <name>CTRL</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                        </cluster>
+                        {}
+                    </registers>
+                </peripheral>
+                ",
+                registers
+            );
+            PeripheralInfo::parse(&Element::parse(xml.as_bytes()).unwrap())
+        };
+
+        let p = peripheral(
+            "
+            <register>
+                <name>CCMR1_Input</name>
+                <alternateRegister>CCMR1_Output</alternateRegister>
+                <addressOffset>0x18</addressOffset>
+            </register>
+            <register>
+                <name>CCMR1</name>
+                <alternateGroup>RAW</alternateGroup>
+                <addressOffset>0x18</addressOffset>
+            </register>
+            <cluster>
+                <name>MODE1</name>
+                <alternateCluster>MODE0</alternateCluster>
+                <addressOffset>0x20</addressOffset>
+                <register>
+                    <name>CTRL</name>
+                    <addressOffset>0x0</addressOffset>
+                </register>
+            </cluster>
+            ",
+        )
+        .unwrap();
+        assert_eq!(PeripheralInfo::parse(&p.encode().unwrap()).unwrap(), p);
+
+        #[cfg(not(feature = "strict"))]
+        peripheral(
+            "
+            <register>
+                <name>CCMR1_Input</name>
+                <alternateRegister>CCMR2_Output</alternateRegister>
+                <addressOffset>0x18</addressOffset>
+            </register>
+            ",
+        )
+        .expect("alternates are only checked with `strict`");
+        #[cfg(feature = "strict")]
+        {
+            peripheral(
+                "
+                <register>
+                    <name>CCMR1_Input</name>
+                    <alternateRegister>CCMR2_Output</alternateRegister>
+                    <addressOffset>0x18</addressOffset>
+                </register>
+                ",
+            )
+            .expect_err("alternate register does not exist");
+            peripheral(
+                "
+                <register>
+                    <name>CCMR1_Input</name>
+                    <alternateRegister>CCMR1_Output</alternateRegister>
+                    <addressOffset>0x1C</addressOffset>
+                </register>
+                ",
+            )
+            .expect_err("alternate register is at another address");
+            peripheral(
+                "
+                <register>
+                    <name>CCMR1</name>
+                    <alternateGroup>RAW</alternateGroup>
+                    <addressOffset>0x1C</addressOffset>
+                </register>
+                ",
+            )
+            .expect_err("nothing to alternate at the address");
+            peripheral(
+                "
+                <cluster>
+                    <name>MODE1</name>
+                    <alternateCluster>MODE0</alternateCluster>
+                    <addressOffset>0x30</addressOffset>
+                    <register>
+                        <name>CTRL</name>
+                        <addressOffset>0x0</addressOffset>
+                    </register>
+                </cluster>
+                ",
+            )
+            .expect_err("alternate cluster is at another address");
+        }
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    fn undeclared_overlap() {
+        use super::*;
+
+        let peripheral = |offset: &str| {
+            let xml = format!(
+                "
+                <peripheral>
+                    <name>TIMER0</name>
+                    <baseAddress>0x40000000</baseAddress>
+                    <size>32</size>
+                    <registers>
+                        <register>
+                            <name>CR</name>
+                            <addressOffset>0x0</addressOffset>
+                        </register>
+                        <cluster>
+                            <name>CH%s</name>
+                            <addressOffset>0x8</addressOffset>
+                            <dim>2</dim>
+                            <dimIncrement>0x10</dimIncrement>
+                            <register>
+                                <name>CTRL</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                        </cluster>
+                        <register>
+                            <name>SR</name>
+                            <addressOffset>{}</addressOffset>
+                        </register>
+                    </registers>
+                </peripheral>
+                ",
+                offset
+            );
+            PeripheralInfo::parse(&Element::parse(xml.as_bytes()).unwrap())
+        };
+
+        peripheral("0x4").unwrap();
+        // between the elements of the cluster array
+        peripheral("0xC").unwrap();
+        peripheral("0x2").expect_err("SR overlaps CR");
+        peripheral("0x18").expect_err("SR overlaps CH1");
+        // arrays are checked without expanding them
+        let arrays = |offset: &str| {
+            let xml = format!(
+                "
+                <peripheral>
+                    <name>TIMER0</name>
+                    <baseAddress>0x40000000</baseAddress>
+                    <size>32</size>
+                    <registers>
+                        <register>
+                            <name>DATA%s</name>
+                            <addressOffset>0x0</addressOffset>
+                            <dim>4294967295</dim>
+                            <dimIncrement>0x8</dimIncrement>
+                        </register>
+                        <register>
+                            <name>FLAGS%s</name>
+                            <addressOffset>{}</addressOffset>
+                            <dim>4294967295</dim>
+                            <dimIncrement>0x8</dimIncrement>
+                        </register>
+                    </registers>
+                </peripheral>
+                ",
+                offset
+            );
+            PeripheralInfo::parse(&Element::parse(xml.as_bytes()).unwrap())
+        };
+        arrays("0x4").unwrap();
+        arrays("0x2").expect_err("FLAGS0 overlaps DATA0");
+    }
+}
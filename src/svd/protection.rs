@@ -0,0 +1,76 @@
+use xmltree::Element;
+
+use crate::elementext::ElementExt;
+use crate::encode::Encode;
+use crate::error::*;
+use crate::new_element;
+use crate::types::Parse;
+
+/// Protection rule required to access an address region
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Protection {
+    /// Secure permission required for access
+    Secure,
+    /// Non-secure or secure permission required for access
+    NonSecure,
+    /// Privileged permission required for access
+    Privileged,
+}
+
+impl Protection {
+    /// Parses the protection from its SVD string representation
+    pub fn parse_str(s: &str) -> Option<Self> {
+        match s {
+            "s" => Some(Protection::Secure),
+            "n" => Some(Protection::NonSecure),
+            "p" => Some(Protection::Privileged),
+            _ => None,
+        }
+    }
+
+    /// Returns the SVD string representation of the protection
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protection::Secure => "s",
+            Protection::NonSecure => "n",
+            Protection::Privileged => "p",
+        }
+    }
+}
+
+impl Parse for Protection {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        let text = tree.get_content()?;
+
+        Self::parse_str(&text).ok_or_else(|| SVDError::InvalidProtection(tree.into(), text).into())
+    }
+}
+
+impl Encode for Protection {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(new_element("protection", Some(self.as_str().to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (Protection::Secure, "<protection>s</protection>"),
+            (Protection::NonSecure, "<protection>n</protection>"),
+            (Protection::Privileged, "<protection>p</protection>"),
+        ];
+
+        run_test::<Protection>(&tests[..]);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::elementext::ElementExt;
+
+use xmltree::Element;
+
+use crate::types::Parse;
+
+use crate::encode::Encode;
+use crate::error::*;
+use crate::new_element;
+
+/// Side effect of reading a register or field
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadAction {
+    /// The register is cleared (set to zero) following a read operation
+    Clear,
+    /// The register is set (set to ones) following a read operation
+    Set,
+    /// The register is modified in some way after a read operation
+    Modify,
+    /// One or more dependent resources other than the current register are immediately affected by a read operation
+    ModifyExternal,
+}
+
+impl Parse for ReadAction {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        use self::ReadAction::*;
+        let text = tree.get_content()?;
+
+        Ok(match text.as_ref() {
+            "clear" => Clear,
+            "set" => Set,
+            "modify" => Modify,
+            "modifyExternal" => ModifyExternal,
+            s => return Err(SVDError::InvalidReadAction(tree.into(), s.into()).into()),
+        })
+    }
+}
+
+impl Encode for ReadAction {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        use self::ReadAction::*;
+        let v = match *self {
+            Clear => "clear",
+            Set => "set",
+            Modify => "modify",
+            ModifyExternal => "modifyExternal",
+        };
+
+        Ok(new_element("readAction", Some(v.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (ReadAction::Clear, "<readAction>clear</readAction>"),
+            (
+                ReadAction::ModifyExternal,
+                "<readAction>modifyExternal</readAction>",
+            ),
+        ];
+
+        run_test::<ReadAction>(&tests[..]);
+    }
+}
@@ -1,3 +1,4 @@
+use core::convert::TryFrom;
 use core::ops::{Deref, DerefMut};
 
 use xmltree::Element;
@@ -9,7 +10,10 @@ use crate::elementext::ElementExt;
 use crate::encode::Encode;
 use crate::error::*;
 use crate::svd::{
-    dimelement::DimElement, registercluster::RegisterCluster, registerinfo::RegisterInfo,
+    dimelement::{expand_name, DimElement},
+    field::Field,
+    registercluster::RegisterCluster,
+    registerinfo::RegisterInfo,
 };
 use anyhow::Result;
 
@@ -40,20 +44,74 @@ impl DerefMut for Register {
     }
 }
 
+impl Register {
+    /// Returns one `Single` register per element of the array,
+    /// with field arrays expanded as well.
+    /// A `Single` register is returned with its fields expanded.
+    /// Expansion stops at the first element whose address offset overflows
+    pub fn expand(&self) -> Vec<Register> {
+        let expand_fields = |mut info: RegisterInfo| {
+            if let Some(fields) = info.fields.as_mut() {
+                *fields = fields.iter().flat_map(Field::expand).collect();
+            }
+            Register::Single(info)
+        };
+        match self {
+            Register::Single(info) => vec![expand_fields(info.clone())],
+            Register::Array(info, array_info) => array_info
+                .indexes()
+                .enumerate()
+                .map_while(|(i, index)| {
+                    let increment = array_info
+                        .dim_increment
+                        .checked_mul(u32::try_from(i).ok()?)?;
+                    let address_offset = info.address_offset.checked_add(increment)?;
+                    let mut info = info.clone();
+                    info.name = expand_name(&info.name, &index);
+                    info.display_name = info.display_name.map(|n| expand_name(&n, &index));
+                    info.address_offset = address_offset;
+                    Some(expand_fields(info))
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Parse for Register {
     type Object = Self;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        assert_eq!(tree.name, "register");
+        if tree.name != "register" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "register".to_string()).into());
+        }
 
-        let info = RegisterInfo::parse(tree)?;
+        Self::_parse(tree, RegisterInfo::parse(tree)?)
+    }
+}
 
+impl Register {
+    /// Parses a register whose fields have already been parsed
+    pub(crate) fn parse_with(tree: &Element, fields: Option<Vec<Field>>) -> Result<Self> {
+        if tree.name != "register" {
+            return Err(SVDError::NotExpectedTag(tree.into(), "register".to_string()).into());
+        }
+        Self::_parse(tree, RegisterInfo::parse_with(tree, fields)?)
+    }
+
+    fn _parse(tree: &Element, info: RegisterInfo) -> Result<Self> {
         if tree.get_child("dimIncrement").is_some() {
             let array_info = DimElement::parse(tree)?;
             check_has_placeholder(&info.name, "register")?;
             if let Some(indices) = &array_info.dim_index {
-                assert_eq!(array_info.dim as usize, indices.len())
+                if array_info.dim as usize != indices.len() {
+                    return Err(SVDError::DimIndexLength(
+                        tree.into(),
+                        array_info.dim,
+                        indices.len(),
+                    )
+                    .into());
+                }
             }
             Ok(Register::Array(info, array_info))
         } else {
@@ -78,43 +136,6 @@ impl Encode for Register {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::dimelement::DimElementBuilder;
-    use crate::registerinfo::RegisterInfoBuilder;
-
-    use crate::run_test;
-    #[test]
-    fn decode_encode() {
-        let tests = vec![(
-            Register::Array(
-                RegisterInfoBuilder::default()
-                    .name("MODE%s".to_string())
-                    .address_offset(8)
-                    .build()
-                    .unwrap(),
-                DimElementBuilder::default()
-                    .dim(2)
-                    .dim_increment(4)
-                    .dim_index(Some(vec!["10".to_string(), "20".to_string()]))
-                    .build()
-                    .unwrap(),
-            ),
-            "
-            <register>
-              <name>MODE%s</name>
-              <addressOffset>0x8</addressOffset>
-              <dim>2</dim>
-              <dimIncrement>4</dimIncrement>
-              <dimIndex>10,20</dimIndex>
-            </register>
-            ",
-        )];
-        run_test::<Register>(&tests[..]);
-    }
-}
-
 /// Register iterator
 pub struct RegIter<'a> {
     pub(crate) rem: Vec<&'a RegisterCluster>,
@@ -162,3 +183,115 @@ impl<'a> std::iter::Iterator for RegIterMut<'a> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimelement::DimElementBuilder;
+    use crate::registerinfo::RegisterInfoBuilder;
+
+    use crate::run_test;
+    use crate::types::DimIndex;
+    #[test]
+    fn decode_encode() {
+        let tests = [(
+            Register::Array(
+                RegisterInfoBuilder::default()
+                    .name("MODE%s".to_string())
+                    .address_offset(8)
+                    .build()
+                    .unwrap(),
+                DimElementBuilder::default()
+                    .dim(2)
+                    .dim_increment(4)
+                    .dim_index(Some(DimIndex::List(vec![
+                        "10".to_string(),
+                        "20".to_string(),
+                    ])))
+                    .build()
+                    .unwrap(),
+            ),
+            "
+            <register>
+              <name>MODE%s</name>
+              <addressOffset>0x8</addressOffset>
+              <dim>2</dim>
+              <dimIncrement>4</dimIncrement>
+              <dimIndex>10,20</dimIndex>
+            </register>
+            ",
+        )];
+        run_test::<Register>(&tests[..]);
+    }
+    #[test]
+    fn expand() {
+        let reg = Register::Array(
+            RegisterInfoBuilder::default()
+                .name("CH[%s]".to_string())
+                .display_name(Some("CH%s".to_string()))
+                .address_offset(8)
+                .build()
+                .unwrap(),
+            DimElementBuilder::default()
+                .dim(3)
+                .dim_increment(4)
+                .build()
+                .unwrap(),
+        );
+        let regs = reg.expand();
+        assert_eq!(regs.len(), 3);
+        for (i, r) in regs.iter().enumerate() {
+            match r {
+                Register::Single(info) => {
+                    assert_eq!(info.name, format!("CH{}", i));
+                    assert_eq!(info.display_name, Some(format!("CH{}", i)));
+                    assert_eq!(info.address_offset, 8 + 4 * i as u32);
+                }
+                Register::Array(..) => panic!("expected single register"),
+            }
+        }
+
+        let mut reg = reg;
+        reg.address_offset = 0xFFFF_FFF8;
+        let offsets: Vec<_> = reg.expand().iter().map(|r| r.address_offset).collect();
+        assert_eq!(offsets, vec![0xFFFF_FFF8, 0xFFFF_FFFC]);
+
+        // expansion stops at the first overflow instead of visiting every element
+        if let Register::Array(_, array_info) = &mut reg {
+            array_info.dim = u32::MAX;
+        }
+        assert_eq!(reg.expand().len(), 2);
+    }
+
+    #[test]
+    fn malformed() {
+        let tree = Element::parse(
+            "
+            <register>
+              <name>MODE%s</name>
+              <addressOffset>0x8</addressOffset>
+              <dim>3</dim>
+              <dimIncrement>4</dimIncrement>
+              <dimIndex>10,20</dimIndex>
+            </register>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let err = Register::parse(&tree).unwrap_err();
+        match err.downcast_ref::<SVDError>() {
+            Some(SVDError::DimIndexLength(elem, 3, 2)) => assert_eq!(elem.tag, "register"),
+            _ => panic!("unexpected {:?}", err),
+        }
+
+        let tree = Element::parse("<field><name>MODE</name></field>".as_bytes()).unwrap();
+        let err = Register::parse(&tree).unwrap_err();
+        match err.downcast_ref::<SVDError>() {
+            Some(SVDError::NotExpectedTag(elem, tag)) => {
+                assert_eq!(elem.tag, "field");
+                assert_eq!(tag, "register");
+            }
+            _ => panic!("unexpected {:?}", err),
+        }
+    }
+}
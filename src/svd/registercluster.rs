@@ -25,6 +25,261 @@ impl From<Cluster> for RegisterCluster {
     }
 }
 
+impl RegisterCluster {
+    /// Returns the registers or clusters with all arrays expanded,
+    /// see [`Register::expand`] and [`Cluster::expand`]
+    pub fn expand(&self) -> Vec<RegisterCluster> {
+        match self {
+            RegisterCluster::Register(r) => r.expand().into_iter().map(Into::into).collect(),
+            RegisterCluster::Cluster(c) => c.expand().into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Checks the alternates among `children` of a peripheral or cluster:
+/// `alternateRegister` and `alternateCluster` must name a sibling at the same
+/// address, a register in an `alternateGroup` must share its address with
+/// another register and overlapping siblings must declare one of these.
+/// `size` is the default register size of the parent
+#[cfg(feature = "strict")]
+pub(crate) fn check_alternates(children: &[RegisterCluster], size: Option<u32>) -> Result<()> {
+    for rc in children {
+        match rc {
+            RegisterCluster::Register(r) => {
+                if let Some(alternate) = &r.alternate_register {
+                    let original = children
+                        .iter()
+                        .find_map(|o| match o {
+                            RegisterCluster::Register(o) if &o.name == alternate => Some(o),
+                            _ => None,
+                        })
+                        .ok_or_else(|| {
+                            AlternateError::NotFound(r.name.clone(), alternate.clone())
+                        })?;
+                    if original.address_offset != r.address_offset {
+                        return Err(AlternateError::DifferentAddress(
+                            r.name.clone(),
+                            alternate.clone(),
+                        )
+                        .into());
+                    }
+                }
+                if let Some(group) = &r.alternate_group {
+                    let shared = children.iter().any(|o| match o {
+                        RegisterCluster::Register(o) => {
+                            !std::ptr::eq(o, r) && o.address_offset == r.address_offset
+                        }
+                        _ => false,
+                    });
+                    if !shared {
+                        return Err(AlternateError::NothingToAlternate(
+                            r.name.clone(),
+                            group.clone(),
+                        )
+                        .into());
+                    }
+                }
+            }
+            RegisterCluster::Cluster(c) => {
+                if let Some(alternate) = &c.alternate_cluster {
+                    let original = children
+                        .iter()
+                        .find_map(|o| match o {
+                            RegisterCluster::Cluster(o) if &o.name == alternate => Some(o),
+                            _ => None,
+                        })
+                        .ok_or_else(|| {
+                            AlternateError::NotFound(c.name.clone(), alternate.clone())
+                        })?;
+                    if original.address_offset != c.address_offset {
+                        return Err(AlternateError::DifferentAddress(
+                            c.name.clone(),
+                            alternate.clone(),
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+    }
+    check_overlaps(children, size)
+}
+
+#[cfg(feature = "strict")]
+fn check_overlaps(children: &[RegisterCluster], size: Option<u32>) -> Result<()> {
+    // Address ranges of the elements which are not declared as alternates
+    let mut spans = Vec::new();
+    for (i, rc) in children.iter().enumerate() {
+        let declared = match rc {
+            RegisterCluster::Register(r) => {
+                r.alternate_register.is_some() || r.alternate_group.is_some()
+            }
+            RegisterCluster::Cluster(c) => c.alternate_cluster.is_some(),
+        };
+        if !declared {
+            spans.extend(Span::new(rc, size).map(|span| (span.hull(), span, i)));
+        }
+    }
+    spans.sort_unstable_by_key(|(hull, ..)| *hull);
+    let name = |rc: &RegisterCluster| match rc {
+        RegisterCluster::Register(r) => r.name.clone(),
+        RegisterCluster::Cluster(c) => c.name.clone(),
+    };
+    for (k, ((_, end), span, i)) in spans.iter().enumerate() {
+        for (_, other, j) in spans[k + 1..]
+            .iter()
+            .take_while(|((start, _), ..)| start < end)
+        {
+            if i != j && span.overlaps(other) {
+                return Err(
+                    AlternateError::Undeclared(name(&children[*i]), name(&children[*j])).into(),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Address ranges `offset + i * stride + [start, end)` of the `count` elements of
+/// a register or cluster relative to its parent, assuming 8-bit address units
+#[cfg(feature = "strict")]
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    offset: u64,
+    stride: u64,
+    count: u64,
+    start: u64,
+    end: u64,
+}
+
+#[cfg(feature = "strict")]
+impl Span {
+    /// Registers with unknown size span one address, clusters span their children.
+    /// Returns `None` for empty arrays
+    fn new(rc: &RegisterCluster, size: Option<u32>) -> Option<Self> {
+        let (offset, dim, start, end) = match rc {
+            RegisterCluster::Register(r) => {
+                let dim = match r {
+                    Register::Single(_) => None,
+                    Register::Array(_, dim) => Some(dim),
+                };
+                let bytes = match r.size.or(size) {
+                    Some(size) if size > 8 => size / 8 + u32::from(size % 8 != 0),
+                    _ => 1,
+                };
+                (r.address_offset, dim, 0, u64::from(bytes))
+            }
+            RegisterCluster::Cluster(c) => {
+                let dim = match c {
+                    Cluster::Single(_) => None,
+                    Cluster::Array(_, dim) => Some(dim),
+                };
+                let size = c.default_register_properties.size.or(size);
+                let inner: Vec<_> = c
+                    .children
+                    .iter()
+                    .filter_map(|rc| Span::new(rc, size))
+                    .map(|span| span.hull())
+                    .collect();
+                let start = inner.iter().map(|s| s.0).min().unwrap_or(0);
+                let end = inner
+                    .iter()
+                    .map(|s| s.1)
+                    .max()
+                    .unwrap_or_else(|| start.saturating_add(1));
+                (c.address_offset, dim, start, end)
+            }
+        };
+        let (count, stride) = match dim {
+            None => (1, 0),
+            Some(dim) => (u64::from(dim.dim), u64::from(dim.dim_increment)),
+        };
+        Some(Self {
+            offset: u64::from(offset),
+            stride,
+            count,
+            start,
+            end,
+        })
+        .filter(|span| span.count > 0)
+    }
+
+    /// Range of the element `i`
+    fn element(&self, i: u64) -> (u64, u64) {
+        let offset = self.offset.saturating_add(i.saturating_mul(self.stride));
+        (
+            offset.saturating_add(self.start),
+            offset.saturating_add(self.end),
+        )
+    }
+
+    /// Range from the start of the first element to the end of the last one
+    fn hull(&self) -> (u64, u64) {
+        (self.element(0).0, self.element(self.count - 1).1)
+    }
+
+    /// Indexes of the first and the last element overlapping `[start, end)`, if any
+    fn elements_in(&self, start: u64, end: u64) -> Option<(u64, u64)> {
+        let (first_start, first_end) = self.element(0);
+        if end <= first_start {
+            return None;
+        }
+        // Last element starting before `end` and first element ending after `start`
+        let last = match (end - 1 - first_start).checked_div(self.stride) {
+            Some(i) => i.min(self.count - 1),
+            None => self.count - 1,
+        };
+        let first = if start < first_end {
+            0
+        } else {
+            (start - first_end).checked_div(self.stride)? + 1
+        };
+        Some((first, last)).filter(|_| first <= last)
+    }
+
+    /// Returns `true` if any elements of the two spans overlap
+    fn overlaps(&self, other: &Span) -> bool {
+        // Elements walked at most, beyond that the spans are taken as overlapping
+        const LIMIT: u64 = 0x10000;
+
+        if self.stride == other.stride && self.stride > 0 {
+            return self.overlaps_same_stride(other);
+        }
+        // Only elements inside the range of the other span can overlap it
+        let candidates = |a: &Span, b: &Span| {
+            let (start, end) = b.hull();
+            a.elements_in(start, end)
+        };
+        let (a, b, (first, last)) = match (candidates(self, other), candidates(other, self)) {
+            (Some(x), Some(y)) if x.1 - x.0 <= y.1 - y.0 => (self, other, x),
+            (Some(_), Some(y)) => (other, self, y),
+            _ => return false,
+        };
+        if last - first >= LIMIT {
+            return true;
+        }
+        (first..=last).any(|i| {
+            let (start, end) = a.element(i);
+            b.elements_in(start, end).is_some()
+        })
+    }
+
+    /// Overlap of two arrays with the same stride: element `i` of `self` overlaps
+    /// element `j` of `other` if the distance of their starts for `k = i - j` is
+    /// between the negated length of `self` and the length of `other`
+    fn overlaps_same_stride(&self, other: &Span) -> bool {
+        let stride = i128::from(self.stride);
+        let distance = i128::from(self.element(0).0) - i128::from(other.element(0).0);
+        let len = i128::from(self.end) - i128::from(self.start);
+        let other_len = i128::from(other.end) - i128::from(other.start);
+        let k_min = (-len - distance).div_euclid(stride) + 1;
+        let k_max = (other_len - distance - 1).div_euclid(stride);
+        let k_min = k_min.max(1 - i128::from(other.count));
+        let k_max = k_max.min(i128::from(self.count) - 1);
+        k_min <= k_max
+    }
+}
+
 impl Parse for RegisterCluster {
     type Object = Self;
     type Error = anyhow::Error;
@@ -35,7 +290,7 @@ impl Parse for RegisterCluster {
         } else if tree.name == "cluster" {
             Ok(RegisterCluster::Cluster(Cluster::parse(tree)?))
         } else {
-            Err(SVDError::InvalidRegisterCluster(tree.clone(), tree.name.clone()).into())
+            Err(SVDError::InvalidRegisterCluster(tree.into(), tree.name.clone()).into())
         }
     }
 }
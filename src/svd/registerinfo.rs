@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -11,8 +11,9 @@ use crate::parse;
 use crate::types::Parse;
 
 use crate::svd::{
-    access::Access, field::Field, modifiedwritevalues::ModifiedWriteValues,
-    registerproperties::RegisterProperties, writeconstraint::WriteConstraint,
+    access::Access, datatype::DataType, field::Field, modifiedwritevalues::ModifiedWriteValues,
+    protection::Protection, readaction::ReadAction, registerproperties::RegisterProperties,
+    vendorextensions::VendorExtensions, writeconstraint::WriteConstraint,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -60,6 +61,11 @@ pub struct RegisterInfo {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub access: Option<Access>,
 
+    /// Protection rule for the register
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub protection: Option<Protection>,
+
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub reset_value: Option<u64>,
@@ -68,6 +74,11 @@ pub struct RegisterInfo {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub reset_mask: Option<u64>,
 
+    /// Register data type used in the device header file
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub data_type: Option<DataType>,
+
     /// `None` indicates that the `<fields>` node is not present
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
@@ -81,6 +92,16 @@ pub struct RegisterInfo {
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub modified_write_values: Option<ModifiedWriteValues>,
+
+    /// Specify the side effect following a read operation
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub read_action: Option<ReadAction>,
+
+    /// Vendor specific extensions of the register, kept as raw XML
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub vendor_extensions: Option<VendorExtensions>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -93,9 +114,12 @@ pub struct RegisterInfoBuilder {
     description: Option<String>,
     display_name: Option<String>,
     properties: RegisterProperties,
+    data_type: Option<DataType>,
     fields: Option<Vec<Field>>,
     write_constraint: Option<WriteConstraint>,
     modified_write_values: Option<ModifiedWriteValues>,
+    read_action: Option<ReadAction>,
+    vendor_extensions: Option<VendorExtensions>,
 }
 
 impl From<RegisterInfo> for RegisterInfoBuilder {
@@ -113,10 +137,14 @@ impl From<RegisterInfo> for RegisterInfoBuilder {
                 access: r.access,
                 reset_value: r.reset_value,
                 reset_mask: r.reset_mask,
+                protection: r.protection,
             },
+            data_type: r.data_type,
             fields: r.fields,
             write_constraint: r.write_constraint,
             modified_write_values: r.modified_write_values,
+            read_action: r.read_action,
+            vendor_extensions: r.vendor_extensions,
         }
     }
 }
@@ -170,6 +198,14 @@ impl RegisterInfoBuilder {
         self.properties.reset_mask = value;
         self
     }
+    pub fn protection(mut self, value: Option<Protection>) -> Self {
+        self.properties.protection = value;
+        self
+    }
+    pub fn data_type(mut self, value: Option<DataType>) -> Self {
+        self.data_type = value;
+        self
+    }
     pub fn fields(mut self, value: Option<Vec<Field>>) -> Self {
         self.fields = value;
         self
@@ -182,7 +218,15 @@ impl RegisterInfoBuilder {
         self.modified_write_values = value;
         self
     }
-    pub fn build(self) -> Result<RegisterInfo> {
+    pub fn read_action(mut self, value: Option<ReadAction>) -> Self {
+        self.read_action = value;
+        self
+    }
+    pub fn vendor_extensions(mut self, value: Option<VendorExtensions>) -> Self {
+        self.vendor_extensions = value;
+        self
+    }
+    pub fn build(self) -> Result<RegisterInfo, Error> {
         (RegisterInfo {
             name: self
                 .name
@@ -199,11 +243,16 @@ impl RegisterInfoBuilder {
             access: self.properties.access,
             reset_value: self.properties.reset_value,
             reset_mask: self.properties.reset_mask,
+            protection: self.properties.protection,
+            data_type: self.data_type,
             fields: self.fields,
             write_constraint: self.write_constraint,
             modified_write_values: self.modified_write_values,
+            read_action: self.read_action,
+            vendor_extensions: self.vendor_extensions,
         })
         .validate()
+        .map_err(Error::from)
     }
 }
 
@@ -234,18 +283,64 @@ impl RegisterInfo {
     }
 }
 
+impl RegisterInfo {
+    /// Returns the register properties specified on the register itself
+    pub fn properties(&self) -> RegisterProperties {
+        RegisterProperties {
+            size: self.size,
+            reset_value: self.reset_value,
+            reset_mask: self.reset_mask,
+            access: self.access,
+            protection: self.protection,
+        }
+    }
+
+    /// Sets the register properties specified on the register itself
+    pub fn set_properties(&mut self, value: &RegisterProperties) {
+        self.size = value.size;
+        self.reset_value = value.reset_value;
+        self.reset_mask = value.reset_mask;
+        self.access = value.access;
+        self.protection = value.protection;
+    }
+}
+
 impl Parse for RegisterInfo {
     type Object = Self;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
         let name = tree.get_child_text("name")?;
-        Self::_parse(tree, name.clone()).with_context(|| format!("In register `{}`", name))
+        Self::_parse(tree, name.clone(), || Self::parse_fields(tree)).in_element("register", &name)
     }
 }
 
 impl RegisterInfo {
-    fn _parse(tree: &Element, name: String) -> Result<Self> {
+    /// Parses a register whose fields have already been parsed
+    pub(crate) fn parse_with(tree: &Element, fields: Option<Vec<Field>>) -> Result<Self> {
+        let name = tree.get_child_text("name")?;
+        Self::_parse(tree, name.clone(), || Ok(fields)).in_element("register", &name)
+    }
+
+    fn parse_fields(tree: &Element) -> Result<Option<Vec<Field>>> {
+        if let Some(fields) = tree.get_child("fields") {
+            let fs: Result<Vec<_>, _> = fields
+                .children
+                .iter()
+                .filter_map(XMLNode::as_element)
+                .enumerate()
+                .map(|(e, t)| Field::parse(t).with_context(|| format!("Parsing field #{}", e)))
+                .collect();
+            Some(fs).transpose()
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn _parse<F>(tree: &Element, name: String, fields: F) -> Result<Self>
+    where
+        F: FnOnce() -> Result<Option<Vec<Field>>>,
+    {
         RegisterInfoBuilder::default()
             .name(name)
             .alternate_group(tree.get_child_text_opt("alternateGroup")?)
@@ -255,27 +350,20 @@ impl RegisterInfo {
             .derived_from(tree.attributes.get("derivedFrom").map(|s| s.to_owned()))
             .address_offset(tree.get_child_u32("addressOffset")?)
             .properties(RegisterProperties::parse(tree)?)
-            .fields({
-                if let Some(fields) = tree.get_child("fields") {
-                    let fs: Result<Vec<_>, _> = fields
-                        .children
-                        .iter()
-                        .enumerate()
-                        .map(|(e, t)| {
-                            Field::parse(t).with_context(|| format!("Parsing field #{}", e))
-                        })
-                        .collect();
-                    Some(fs?)
-                } else {
-                    None
-                }
-            })
+            .data_type(parse::optional::<DataType>("dataType", tree)?)
+            .fields(fields()?)
             .write_constraint(parse::optional::<WriteConstraint>("writeConstraint", tree)?)
             .modified_write_values(parse::optional::<ModifiedWriteValues>(
                 "modifiedWriteValues",
                 tree,
             )?)
+            .read_action(parse::optional::<ReadAction>("readAction", tree)?)
+            .vendor_extensions(parse::optional::<VendorExtensions>(
+                "vendorExtensions",
+                tree,
+            )?)
             .build()
+            .map_err(Error::into_anyhow)
     }
 }
 
@@ -289,54 +377,88 @@ impl Encode for RegisterInfo {
             namespaces: None,
             name: String::from("register"),
             attributes: HashMap::new(),
-            children: vec![
-                new_element("name", Some(self.name.clone())),
-                new_element(
-                    "addressOffset",
-                    Some(format!("0x{:x}", self.address_offset)),
-                ),
-            ],
-            text: None,
+            children: vec![XMLNode::Element(new_element(
+                "name",
+                Some(self.name.clone()),
+            ))],
         };
-        if let Some(v) = &self.description {
-            elem.children
-                .push(new_element("description", Some(v.clone())));
-        }
         if let Some(v) = &self.display_name {
-            elem.children
-                .push(new_element("displayName", Some(v.clone())));
+            elem.children.push(XMLNode::Element(new_element(
+                "displayName",
+                Some(v.clone()),
+            )));
+        }
+        if let Some(v) = &self.description {
+            elem.children.push(XMLNode::Element(new_element(
+                "description",
+                Some(v.clone()),
+            )));
         }
         if let Some(v) = &self.alternate_group {
-            elem.children
-                .push(new_element("alternateGroup", Some(v.to_string())));
+            elem.children.push(XMLNode::Element(new_element(
+                "alternateGroup",
+                Some(v.to_string()),
+            )));
         }
 
         if let Some(v) = &self.alternate_register {
-            elem.children
-                .push(new_element("alternateRegister", Some(v.to_string())));
+            elem.children.push(XMLNode::Element(new_element(
+                "alternateRegister",
+                Some(v.to_string()),
+            )));
         }
 
+        elem.children.push(XMLNode::Element(new_element(
+            "addressOffset",
+            Some(format!("0x{:x}", self.address_offset)),
+        )));
+
         if let Some(v) = &self.derived_from {
             elem.attributes
                 .insert(String::from("derivedFrom"), v.to_string());
         }
 
         if let Some(v) = &self.size {
-            elem.children.push(new_element("size", Some(v.to_string())));
+            elem.children
+                .push(XMLNode::Element(new_element("size", Some(v.to_string()))));
         };
 
         if let Some(v) = &self.access {
-            elem.children.push(v.encode()?);
+            elem.children.push(XMLNode::Element(v.encode()?));
+        };
+
+        if let Some(v) = &self.protection {
+            elem.children.push(XMLNode::Element(v.encode()?));
         };
 
         if let Some(v) = &self.reset_value {
-            elem.children
-                .push(new_element("resetValue", Some(format!("0x{:08.x}", v))));
+            elem.children.push(XMLNode::Element(new_element(
+                "resetValue",
+                Some(format!("0x{:08.x}", v)),
+            )));
         };
 
         if let Some(v) = &self.reset_mask {
-            elem.children
-                .push(new_element("resetMask", Some(format!("0x{:08.x}", v))));
+            elem.children.push(XMLNode::Element(new_element(
+                "resetMask",
+                Some(format!("0x{:08.x}", v)),
+            )));
+        };
+
+        if let Some(v) = &self.data_type {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        };
+
+        if let Some(v) = &self.modified_write_values {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        };
+
+        if let Some(v) = &self.write_constraint {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        };
+
+        if let Some(v) = &self.read_action {
+            elem.children.push(XMLNode::Element(v.encode()?));
         };
 
         if let Some(v) = &self.fields {
@@ -351,20 +473,15 @@ impl Encode for RegisterInfo {
                     namespaces: None,
                     name: String::from("fields"),
                     attributes: HashMap::new(),
-                    children,
-                    text: None,
+                    children: children.into_iter().map(XMLNode::Element).collect(),
                 };
-                elem.children.push(fields);
+                elem.children.push(XMLNode::Element(fields));
             }
         };
 
-        if let Some(v) = &self.write_constraint {
-            elem.children.push(v.encode()?);
-        };
-
-        if let Some(v) = &self.modified_write_values {
-            elem.children.push(v.encode()?);
-        };
+        if let Some(v) = &self.vendor_extensions {
+            elem.children.push(XMLNode::Element(v.encode()?));
+        }
 
         Ok(elem)
     }
@@ -373,13 +490,13 @@ impl Encode for RegisterInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::run_test;
     use crate::svd::bitrange::*;
     use crate::svd::fieldinfo::FieldInfoBuilder;
+    use crate::{run_order_test, run_test};
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
+        let tests = [(
             RegisterInfoBuilder::default()
                 .name("WRITECTRL".to_string())
                 .alternate_group(Some("alternate_group".to_string()))
@@ -391,6 +508,8 @@ mod tests {
                 .access(Some(Access::ReadWrite))
                 .reset_value(Some(0x00000000))
                 .reset_mask(Some(0x00000023))
+                .protection(Some(Protection::Secure))
+                .data_type(Some(DataType::U32))
                 .fields(Some(vec![Field::Single(
                     FieldInfoBuilder::default()
                         .name("WREN".to_string())
@@ -401,23 +520,33 @@ mod tests {
                             range_type: BitRangeType::OffsetWidth,
                         })
                         .access(Some(Access::ReadWrite))
+                        .read_action(Some(ReadAction::Clear))
                         .build()
                         .unwrap(),
                 )]))
                 .modified_write_values(Some(ModifiedWriteValues::OneToToggle))
+                .write_constraint(Some(WriteConstraint::WriteAsRead(true)))
+                .read_action(Some(ReadAction::Modify))
                 .build()
                 .unwrap(),
             "
             <register derivedFrom=\"derived_from\">
                 <name>WRITECTRL</name>
                 <description>Write Control Register</description>
-                <addressOffset>0x8</addressOffset>
                 <alternateGroup>alternate_group</alternateGroup>
                 <alternateRegister>alternate_register</alternateRegister>
+                <addressOffset>0x8</addressOffset>
                 <size>32</size>
                 <access>read-write</access>
+                <protection>s</protection>
                 <resetValue>0x00000000</resetValue>
                 <resetMask>0x00000023</resetMask>
+                <dataType>uint32_t</dataType>
+                <modifiedWriteValues>oneToToggle</modifiedWriteValues>
+                <writeConstraint>
+                    <writeAsRead>true</writeAsRead>
+                </writeConstraint>
+                <readAction>modify</readAction>
                 <fields>
                     <field>
                         <name>WREN</name>
@@ -425,13 +554,15 @@ mod tests {
                         <bitOffset>0</bitOffset>
                         <bitWidth>1</bitWidth>
                         <access>read-write</access>
+                        <readAction>clear</readAction>
                     </field>
                 </fields>
-                <modifiedWriteValues>oneToToggle</modifiedWriteValues>
             </register>
             ",
         )];
 
         run_test::<RegisterInfo>(&tests[..]);
+
+        run_order_test(&tests[..]);
     }
 }
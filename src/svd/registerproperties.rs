@@ -8,7 +8,7 @@ use crate::new_element;
 use crate::parse;
 use crate::types::Parse;
 
-use crate::svd::access::Access;
+use crate::svd::{access::Access, protection::Protection};
 
 /// Register default properties
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -34,6 +34,11 @@ pub struct RegisterProperties {
     #[cfg_attr(feature = "serde", serde(default))]
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub access: Option<Access>,
+
+    /// Default protection rule for all registers
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub protection: Option<Protection>,
 }
 
 impl Parse for RegisterProperties {
@@ -46,6 +51,7 @@ impl Parse for RegisterProperties {
             reset_value: parse::optional::<u64>("resetValue", tree)?,
             reset_mask: parse::optional::<u64>("resetMask", tree)?,
             access: parse::optional::<Access>("access", tree)?,
+            protection: parse::optional::<Protection>("protection", tree)?,
         };
         check_reset_value(p.size, p.reset_value, p.reset_mask)?;
         Ok(p)
@@ -62,6 +68,14 @@ impl EncodeChildren for RegisterProperties {
             children.push(new_element("size", Some(format!("0x{:08.x}", v))));
         };
 
+        if let Some(v) = &self.access {
+            children.push(v.encode()?);
+        };
+
+        if let Some(v) = &self.protection {
+            children.push(v.encode()?);
+        };
+
         if let Some(v) = &self.reset_value {
             children.push(new_element("resetValue", Some(format!("0x{:08.x}", v))));
         };
@@ -70,10 +84,6 @@ impl EncodeChildren for RegisterProperties {
             children.push(new_element("resetMask", Some(format!("0x{:08.x}", v))));
         };
 
-        if let Some(v) = &self.access {
-            children.push(v.encode()?);
-        };
-
         Ok(children)
     }
 }
@@ -81,6 +91,7 @@ impl EncodeChildren for RegisterProperties {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use xmltree::XMLNode;
 
     #[test]
     fn decode_encode() {
@@ -88,18 +99,21 @@ mod tests {
             "
             <mock>
                 <size>0xaabbccdd</size>
+                <access>read-only</access>
+                <protection>p</protection>
                 <resetValue>0x11223344</resetValue>
                 <resetMask>0xffffffff</resetMask>
-                <access>read-only</access>
             </mock>
         ",
         );
 
-        let mut expected = RegisterProperties::default();
-        expected.size = Some(0xaabbccdd);
-        expected.reset_value = Some(0x11223344);
-        expected.reset_mask = Some(0xffffffff);
-        expected.access = Some(Access::ReadOnly);
+        let expected = RegisterProperties {
+            size: Some(0xaabbccdd),
+            reset_value: Some(0x11223344),
+            reset_mask: Some(0xffffffff),
+            access: Some(Access::ReadOnly),
+            protection: Some(Protection::Privileged),
+        };
 
         let tree1 = Element::parse(example.as_bytes()).unwrap();
 
@@ -107,7 +121,12 @@ mod tests {
         assert_eq!(parsed, expected, "Parsing tree failed");
 
         let mut tree2 = new_element("mock", None);
-        tree2.children = parsed.encode().unwrap();
+        tree2.children = parsed
+            .encode()
+            .unwrap()
+            .into_iter()
+            .map(XMLNode::Element)
+            .collect();
         assert_eq!(tree1, tree2, "Encoding value failed");
     }
 }
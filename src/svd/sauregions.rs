@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use xmltree::{Element, XMLNode};
+
+use crate::elementext::ElementExt;
+use crate::encode::Encode;
+use crate::error::*;
+use crate::new_element;
+use crate::types::Parse;
+
+use crate::svd::protection::Protection;
+
+/// Configuration of the Security Attribution Unit (SAU) regions
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct SauRegionsConfig {
+    /// Specify whether the SAU is enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub enabled: Option<bool>,
+
+    /// Set the protection mode for disabled regions
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub protection_when_disabled: Option<Protection>,
+
+    /// Group to configure SAU regions
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub regions: Vec<SauRegion>,
+}
+
+/// Security Attribution Unit (SAU) region
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct SauRegion {
+    /// Specify whether the region is enabled
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub enabled: Option<bool>,
+
+    /// Identifier of the region
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+
+    /// Base address of the region
+    pub base: u32,
+
+    /// Limit address of the region
+    pub limit: u32,
+
+    /// Access permissions of the region
+    pub access: SauAccess,
+}
+
+/// Access permissions of a SAU region
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SauAccess {
+    /// Non-secure accessible
+    NonSecure,
+    /// Non-secure callable
+    NonSecureCallable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SauRegionError {
+    #[error("SAU region limit 0x{1:08x} is below base 0x{0:08x}")]
+    LimitBelowBase(u32, u32),
+    #[error("{0} SAU regions configured, but the device only has {1}")]
+    TooManyRegions(usize, u32),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SauRegionsConfigBuilder {
+    enabled: Option<bool>,
+    protection_when_disabled: Option<Protection>,
+    regions: Option<Vec<SauRegion>>,
+}
+
+impl From<SauRegionsConfig> for SauRegionsConfigBuilder {
+    fn from(s: SauRegionsConfig) -> Self {
+        Self {
+            enabled: s.enabled,
+            protection_when_disabled: s.protection_when_disabled,
+            regions: Some(s.regions),
+        }
+    }
+}
+
+impl SauRegionsConfigBuilder {
+    pub fn enabled(mut self, value: Option<bool>) -> Self {
+        self.enabled = value;
+        self
+    }
+    pub fn protection_when_disabled(mut self, value: Option<Protection>) -> Self {
+        self.protection_when_disabled = value;
+        self
+    }
+    pub fn regions(mut self, value: Vec<SauRegion>) -> Self {
+        self.regions = Some(value);
+        self
+    }
+    pub fn build(self) -> Result<SauRegionsConfig, Error> {
+        Ok(SauRegionsConfig {
+            enabled: self.enabled,
+            protection_when_disabled: self.protection_when_disabled,
+            regions: self.regions.unwrap_or_default(),
+        })
+    }
+}
+
+impl SauRegionsConfig {
+    #[cfg(feature = "strict")]
+    pub(crate) fn check_num_regions(&self, num_regions: u32) -> Result<()> {
+        if self.regions.len() > num_regions as usize {
+            return Err(SauRegionError::TooManyRegions(self.regions.len(), num_regions).into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SauRegionBuilder {
+    enabled: Option<bool>,
+    name: Option<String>,
+    base: Option<u32>,
+    limit: Option<u32>,
+    access: Option<SauAccess>,
+}
+
+impl From<SauRegion> for SauRegionBuilder {
+    fn from(r: SauRegion) -> Self {
+        Self {
+            enabled: r.enabled,
+            name: r.name,
+            base: Some(r.base),
+            limit: Some(r.limit),
+            access: Some(r.access),
+        }
+    }
+}
+
+impl SauRegionBuilder {
+    pub fn enabled(mut self, value: Option<bool>) -> Self {
+        self.enabled = value;
+        self
+    }
+    pub fn name(mut self, value: Option<String>) -> Self {
+        self.name = value;
+        self
+    }
+    pub fn base(mut self, value: u32) -> Self {
+        self.base = Some(value);
+        self
+    }
+    pub fn limit(mut self, value: u32) -> Self {
+        self.limit = Some(value);
+        self
+    }
+    pub fn access(mut self, value: SauAccess) -> Self {
+        self.access = Some(value);
+        self
+    }
+    pub fn build(self) -> Result<SauRegion, Error> {
+        (SauRegion {
+            enabled: self.enabled,
+            name: self.name,
+            base: self
+                .base
+                .ok_or_else(|| BuildError::Uninitialized("base".to_string()))?,
+            limit: self
+                .limit
+                .ok_or_else(|| BuildError::Uninitialized("limit".to_string()))?,
+            access: self
+                .access
+                .ok_or_else(|| BuildError::Uninitialized("access".to_string()))?,
+        })
+        .validate()
+        .map_err(Error::from)
+    }
+}
+
+impl SauRegion {
+    fn validate(self) -> Result<Self> {
+        // The low 5 bits of both addresses are ignored,
+        // so a region with `limit` equal to `base` spans 32 bytes
+        #[cfg(feature = "strict")]
+        if self.limit < self.base {
+            return Err(SauRegionError::LimitBelowBase(self.base, self.limit).into());
+        }
+        Ok(self)
+    }
+}
+
+/// Parses an optional boolean attribute
+fn bool_attribute(tree: &Element, name: &str) -> Result<Option<bool>> {
+    match tree.attributes.get(name) {
+        None => Ok(None),
+        Some(text) => match text.as_str() {
+            "0" => Ok(Some(false)),
+            "1" => Ok(Some(true)),
+            _ => match text.parse() {
+                Ok(b) => Ok(Some(b)),
+                Err(e) => Err(SVDError::InvalidBooleanValue(tree.into(), text.clone(), e).into()),
+            },
+        },
+    }
+}
+
+impl Parse for SauRegionsConfig {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        if tree.name != "sauRegionsConfig" {
+            return Err(
+                SVDError::NotExpectedTag(tree.into(), "sauRegionsConfig".to_string()).into(),
+            );
+        }
+        SauRegionsConfigBuilder::default()
+            .enabled(bool_attribute(tree, "enabled")?)
+            .protection_when_disabled(match tree.attributes.get("protectionWhenDisabled") {
+                Some(s) => Some(
+                    Protection::parse_str(s)
+                        .ok_or_else(|| SVDError::InvalidProtection(tree.into(), s.clone()))?,
+                ),
+                None => None,
+            })
+            .regions({
+                let regions: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter_map(XMLNode::as_element)
+                    .filter(|t| t.name == "region")
+                    .enumerate()
+                    .map(|(e, t)| {
+                        SauRegion::parse(t).with_context(|| format!("Parsing SAU region #{}", e))
+                    })
+                    .collect();
+                regions?
+            })
+            .build()
+            .map_err(Error::into_anyhow)
+    }
+}
+
+impl Encode for SauRegionsConfig {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut elem = new_element("sauRegionsConfig", None);
+        if let Some(v) = &self.enabled {
+            elem.attributes
+                .insert(String::from("enabled"), format!("{}", v));
+        }
+        if let Some(v) = &self.protection_when_disabled {
+            elem.attributes.insert(
+                String::from("protectionWhenDisabled"),
+                v.as_str().to_string(),
+            );
+        }
+        let regions: Result<Vec<_>, _> = self.regions.iter().map(SauRegion::encode).collect();
+        elem.children = regions?.into_iter().map(XMLNode::Element).collect();
+        Ok(elem)
+    }
+}
+
+impl Parse for SauRegion {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        SauRegionBuilder::default()
+            .enabled(bool_attribute(tree, "enabled")?)
+            .name(tree.attributes.get("name").cloned())
+            .base(tree.get_child_u32("base")?)
+            .limit(tree.get_child_u32("limit")?)
+            .access(SauAccess::parse(tree.get_child_elem("access")?)?)
+            .build()
+            .map_err(Error::into_anyhow)
+    }
+}
+
+impl Encode for SauRegion {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut attributes = HashMap::new();
+        if let Some(v) = &self.enabled {
+            attributes.insert(String::from("enabled"), format!("{}", v));
+        }
+        if let Some(v) = &self.name {
+            attributes.insert(String::from("name"), v.clone());
+        }
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("region"),
+            attributes,
+            children: vec![
+                XMLNode::Element(new_element("base", Some(format!("0x{:08X}", self.base)))),
+                XMLNode::Element(new_element("limit", Some(format!("0x{:08X}", self.limit)))),
+                XMLNode::Element(self.access.encode()?),
+            ],
+        })
+    }
+}
+
+impl Parse for SauAccess {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        let text = tree.get_content()?;
+
+        match &text[..] {
+            "n" => Ok(SauAccess::NonSecure),
+            "c" => Ok(SauAccess::NonSecureCallable),
+            _ => Err(SVDError::UnknownAccessType(tree.into(), text).into()),
+        }
+    }
+}
+
+impl Encode for SauAccess {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let text = match *self {
+            SauAccess::NonSecure => "n",
+            SauAccess::NonSecureCallable => "c",
+        };
+
+        Ok(new_element("access", Some(text.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [(
+            SauRegionsConfigBuilder::default()
+                .enabled(Some(true))
+                .protection_when_disabled(Some(Protection::Secure))
+                .regions(vec![
+                    SauRegionBuilder::default()
+                        .enabled(Some(true))
+                        .name(Some("SAU1".to_string()))
+                        .base(0x10001000)
+                        .limit(0x10005000)
+                        .access(SauAccess::NonSecure)
+                        .build()
+                        .unwrap(),
+                    SauRegionBuilder::default()
+                        .base(0x20000000)
+                        .limit(0x2000001F)
+                        .access(SauAccess::NonSecureCallable)
+                        .build()
+                        .unwrap(),
+                ])
+                .build()
+                .unwrap(),
+            "
+            <sauRegionsConfig enabled=\"true\" protectionWhenDisabled=\"s\">
+                <region enabled=\"true\" name=\"SAU1\">
+                    <base>0x10001000</base>
+                    <limit>0x10005000</limit>
+                    <access>n</access>
+                </region>
+                <region>
+                    <base>0x20000000</base>
+                    <limit>0x2000001F</limit>
+                    <access>c</access>
+                </region>
+            </sauRegionsConfig>
+            ",
+        )];
+
+        run_test::<SauRegionsConfig>(&tests[..]);
+    }
+
+    #[test]
+    fn invalid_region() {
+        let region = |base, limit| {
+            SauRegionBuilder::default()
+                .base(base)
+                .limit(limit)
+                .access(SauAccess::NonSecure)
+                .build()
+        };
+        region(0x10005000, 0x10005000).expect("a region may span a single granule");
+        #[cfg(feature = "strict")]
+        region(0x10005000, 0x10001000).expect_err("limit must not be below base");
+        #[cfg(not(feature = "strict"))]
+        region(0x10005000, 0x10001000).expect("regions are only checked with `strict`");
+    }
+}
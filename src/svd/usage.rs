@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -20,13 +20,13 @@ impl Parse for Usage {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         match &text[..] {
             "read" => Ok(Usage::Read),
             "write" => Ok(Usage::Write),
             "read-write" => Ok(Usage::ReadWrite),
-            _ => Err(SVDError::UnknownUsageVariant(tree.clone()).into()),
+            _ => Err(SVDError::UnknownUsageVariant(tree.into()).into()),
         }
     }
 }
@@ -47,8 +47,7 @@ impl Encode for Usage {
             namespaces: None,
             name: String::from("usage"),
             attributes: HashMap::new(),
-            children: Vec::new(),
-            text: Some(text),
+            children: vec![XMLNode::Text(text)],
         })
     }
 }
@@ -60,7 +59,7 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![
+        let tests = [
             (Usage::Read, "<usage>read</usage>"),
             (Usage::Write, "<usage>write</usage>"),
             (Usage::ReadWrite, "<usage>read-write</usage>"),
@@ -0,0 +1,416 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use xmltree::{Element, XMLNode};
+
+use crate::parse::Config;
+use crate::svd::{ClusterInfo, Device, FieldInfo, PeripheralInfo, RegisterCluster, RegisterInfo};
+use crate::types::Parse;
+
+use crate::encode::Encode;
+use crate::error::*;
+
+/// Vendor specific extensions, kept as the raw XML found in `<vendorExtensions>`.
+/// With the `serde` feature they are (de)serialized as an XML string
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct VendorExtensions {
+    /// Children of the `vendorExtensions` element: elements, text and comments.
+    /// CDATA sections are kept as text
+    pub children: Vec<XMLNode>,
+
+    // Values produced by the extension parsers registered in `Config`
+    typed: Vec<TypedExtension>,
+}
+
+/// Value produced by an extension parser. It is unwind safe so that `Device` stays so
+pub(crate) type TypedExtension = Arc<dyn Any + Send + Sync + UnwindSafe + RefUnwindSafe>;
+
+/// Typed model of a vendor extension.
+///
+/// Types implementing it can be registered with [`Config::extension`]
+/// and are parsed from the `<vendorExtensions>` element of the device.
+/// The parsed value is available with [`Device::extension`](crate::svd::Device::extension)
+pub trait VendorExtension:
+    Parse<Object = Self, Error = anyhow::Error> + Any + Debug + Send + Sync + UnwindSafe + RefUnwindSafe
+{
+}
+
+impl VendorExtensions {
+    /// Creates vendor extensions from XML nodes
+    pub fn new(children: Vec<XMLNode>) -> Self {
+        Self {
+            children,
+            typed: Vec::new(),
+        }
+    }
+
+    /// Returns the child elements, skipping text and comments
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(XMLNode::as_element)
+    }
+
+    /// Returns the value parsed by the registered extension parser of type `T`
+    pub fn get<T: VendorExtension>(&self) -> Option<&T> {
+        self.typed.iter().find_map(|v| {
+            let v: &(dyn Any + Send + Sync) = &**v;
+            v.downcast_ref::<T>()
+        })
+    }
+
+    /// Runs the extension parsers of `config` on the `<vendorExtensions>` element
+    pub(crate) fn parse_registered(&mut self, tree: &Element, config: &Config) -> Result<()> {
+        for parse in &config.extensions {
+            self.typed.push(parse(tree)?);
+        }
+        Ok(())
+    }
+
+    /// Parses vendor extensions from a `<vendorExtensions>` XML string
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        Self::parse(&Element::parse(xml.as_bytes())?)
+    }
+
+    /// Returns the `<vendorExtensions>` element as an XML string
+    pub fn to_xml(&self) -> Result<String> {
+        let mut wr = Vec::new();
+        self.encode()?.write(&mut wr)?;
+        let xml = String::from_utf8(wr)?;
+        // `Element::write` always starts a new document, drop its declaration
+        Ok(match xml.find("?>") {
+            Some(end) if xml.starts_with("<?xml") => xml[end + 2..].to_string(),
+            _ => xml,
+        })
+    }
+}
+
+/// Runs the extension parsers of `config` on the vendor extensions of `device`
+/// and of all its peripherals, clusters, registers and fields.
+///
+/// Returns the errors of the vendor extensions which fail to parse,
+/// with the path of the element containing them
+pub(crate) fn parse_extensions(device: &mut Device, config: &Config) -> Vec<anyhow::Error> {
+    struct Walk<'a> {
+        config: &'a Config,
+        path: Vec<PathElement>,
+        errors: Vec<anyhow::Error>,
+    }
+
+    impl Walk<'_> {
+        // Enters the element and parses its vendor extensions, the caller leaves it
+        fn enter(&mut self, tag: &'static str, name: &str, ext: &mut Option<VendorExtensions>) {
+            self.path.push(PathElement {
+                tag,
+                name: name.to_string(),
+            });
+            if let Some(ext) = ext {
+                let result = ext
+                    .encode()
+                    .and_then(|tree| ext.parse_registered(&tree, self.config));
+                if let Err(e) = result {
+                    self.errors.push(e.context(ElementPath(self.path.clone())));
+                }
+            }
+        }
+
+        fn register_cluster(&mut self, rc: &mut RegisterCluster) {
+            match rc {
+                RegisterCluster::Register(r) => {
+                    let info: &mut RegisterInfo = r;
+                    self.enter("register", &info.name, &mut info.vendor_extensions);
+                    for f in info.fields.iter_mut().flatten() {
+                        let info: &mut FieldInfo = f;
+                        self.enter("field", &info.name, &mut info.vendor_extensions);
+                        self.path.pop();
+                    }
+                }
+                RegisterCluster::Cluster(c) => {
+                    let info: &mut ClusterInfo = c;
+                    self.enter("cluster", &info.name, &mut info.vendor_extensions);
+                    for rc in &mut info.children {
+                        self.register_cluster(rc);
+                    }
+                }
+            }
+            self.path.pop();
+        }
+    }
+
+    if config.extensions.is_empty() {
+        return Vec::new();
+    }
+    let mut walk = Walk {
+        config,
+        path: Vec::new(),
+        errors: Vec::new(),
+    };
+    walk.enter("device", &device.name, &mut device.vendor_extensions);
+    for p in &mut device.peripherals {
+        let info: &mut PeripheralInfo = p;
+        walk.enter("peripheral", &info.name, &mut info.vendor_extensions);
+        for rc in info.registers.iter_mut().flatten() {
+            walk.register_cluster(rc);
+        }
+        walk.path.pop();
+    }
+    walk.errors
+}
+
+// Typed values are opaque, print the XML only
+impl Debug for VendorExtensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("VendorExtensions")
+            .field("children", &self.children)
+            .finish_non_exhaustive()
+    }
+}
+
+// Namespace scopes are inherited from the enclosing document, so they are
+// not part of the extension content and are ignored when comparing
+impl PartialEq for VendorExtensions {
+    fn eq(&self, other: &Self) -> bool {
+        fn same(a: &Element, b: &Element) -> bool {
+            a.prefix == b.prefix
+                && a.namespace == b.namespace
+                && a.name == b.name
+                && a.attributes == b.attributes
+                && a.children.len() == b.children.len()
+                && a.children
+                    .iter()
+                    .zip(&b.children)
+                    .all(|(a, b)| same_node(a, b))
+        }
+        fn same_node(a: &XMLNode, b: &XMLNode) -> bool {
+            match (a, b) {
+                (XMLNode::Element(a), XMLNode::Element(b)) => same(a, b),
+                _ => a == b,
+            }
+        }
+        self.children.len() == other.children.len()
+            && self
+                .children
+                .iter()
+                .zip(&other.children)
+                .all(|(a, b)| same_node(a, b))
+    }
+}
+
+impl Parse for VendorExtensions {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        if tree.name != "vendorExtensions" {
+            return Err(
+                SVDError::NotExpectedTag(tree.into(), "vendorExtensions".to_string()).into(),
+            );
+        }
+        Ok(Self::new(tree.children.clone()))
+    }
+}
+
+impl Encode for VendorExtensions {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("vendorExtensions"),
+            attributes: HashMap::new(),
+            children: self.children.clone(),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for VendorExtensions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let xml = self.to_xml().map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&xml)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for VendorExtensions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let xml = String::deserialize(deserializer)?;
+        Self::from_xml(&xml).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [(
+            VendorExtensions::new(vec![XMLNode::Element(
+                Element::parse(
+                    "<clock name=\"HFXO\"><frequency>38400000</frequency></clock>".as_bytes(),
+                )
+                .unwrap(),
+            )]),
+            "
+            <vendorExtensions>
+                <clock name=\"HFXO\">
+                    <frequency>38400000</frequency>
+                </clock>
+            </vendorExtensions>
+            ",
+        )];
+
+        run_test::<VendorExtensions>(&tests[..]);
+    }
+
+    #[test]
+    fn text_and_comments() {
+        let xml = "<vendorExtensions><!-- pins -->PA0<pin name=\"PA0\" /><![CDATA[<raw>]]></vendorExtensions>";
+        let ext = VendorExtensions::from_xml(xml).unwrap();
+        assert_eq!(
+            ext.children,
+            vec![
+                XMLNode::Comment(" pins ".to_string()),
+                XMLNode::Text("PA0".to_string()),
+                XMLNode::Element(Element::parse("<pin name=\"PA0\"/>".as_bytes()).unwrap()),
+                XMLNode::Text("<raw>".to_string()),
+            ]
+        );
+        assert_eq!(ext.elements().count(), 1);
+        let encoded = ext.to_xml().unwrap();
+        assert_eq!(
+            encoded,
+            "<vendorExtensions><!-- pins -->PA0<pin name=\"PA0\" />&lt;raw&gt;</vendorExtensions>"
+        );
+        assert_eq!(VendorExtensions::from_xml(&encoded).unwrap(), ext);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let ext =
+            VendorExtensions::from_xml("<vendorExtensions><pin name=\"PA0\"/></vendorExtensions>")
+                .unwrap();
+        let json = serde_json::to_string(&ext).unwrap();
+        assert_eq!(
+            json,
+            "\"<vendorExtensions><pin name=\\\"PA0\\\" /></vendorExtensions>\""
+        );
+        assert_eq!(
+            serde_json::from_str::<VendorExtensions>(&json).unwrap(),
+            ext
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Clocks(Vec<String>);
+
+    impl Parse for Clocks {
+        type Object = Self;
+        type Error = anyhow::Error;
+
+        fn parse(tree: &Element) -> Result<Self> {
+            let clocks = match tree.get_child("clocks") {
+                Some(clocks) => clocks,
+                None => return Ok(Self(Vec::new())),
+            };
+            clocks
+                .children
+                .iter()
+                .filter_map(XMLNode::as_element)
+                .map(|c| {
+                    c.attributes
+                        .get("name")
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("clock without name"))
+                })
+                .collect::<Result<_>>()
+                .map(Self)
+        }
+    }
+
+    impl VendorExtension for Clocks {}
+
+    #[test]
+    fn typed_extension() {
+        let svd = |clock: &str| {
+            format!(
+                "
+                <device>
+                    <name>TEST</name>
+                    <peripherals>
+                        <peripheral>
+                            <name>TIMER0</name>
+                            <baseAddress>0x40000000</baseAddress>
+                        </peripheral>
+                    </peripherals>
+                    <vendorExtensions>
+                        <clocks>{}</clocks>
+                    </vendorExtensions>
+                </device>
+                ",
+                clock
+            )
+        };
+        let config = Config::default().extension::<Clocks>();
+
+        let device = crate::parse(&svd("<clock name=\"HFXO\"/>")).unwrap();
+        assert_eq!(device.extension::<Clocks>(), None);
+
+        let device = crate::parse_with_config(&svd("<clock name=\"HFXO\"/>"), &config).unwrap();
+        assert_eq!(
+            device.extension::<Clocks>(),
+            Some(&Clocks(vec!["HFXO".to_string()]))
+        );
+        assert_eq!(device.clone().extension::<Clocks>(), device.extension());
+        // `Device` stays usable in `catch_unwind`
+        let clocks = std::panic::catch_unwind(|| device.extension::<Clocks>().map(|c| c.0.len()));
+        assert_eq!(clocks.unwrap(), Some(1));
+
+        crate::parse_with_config(&svd("<clock/>"), &config).expect_err("clock without name");
+    }
+
+    #[test]
+    fn typed_extension_of_elements() {
+        let svd = "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIMER0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR</name>
+          <addressOffset>0x0</addressOffset>
+          <vendorExtensions><clocks><clock/></clocks></vendorExtensions>
+        </register>
+      </registers>
+      <vendorExtensions><clocks><clock name=\"HFXO\"/></clocks></vendorExtensions>
+    </peripheral>
+  </peripherals>
+</device>";
+        let config = Config::default().extension::<Clocks>();
+
+        let err = crate::parse_with_config(svd, &config).expect_err("clock without name");
+        let path: Vec<_> = err.path().into_iter().map(|p| p.name).collect();
+        assert_eq!(path, ["TEST", "TIMER0", "CR"]);
+        assert_eq!(err.location().unwrap().span.line, 8);
+
+        let (device, diagnostics) = crate::parse_with_diagnostics(svd, &config);
+        let device = device.unwrap();
+        let timer0 = device.peripherals[0].vendor_extensions.as_ref().unwrap();
+        assert_eq!(
+            timer0.get::<Clocks>(),
+            Some(&Clocks(vec!["HFXO".to_string()]))
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path_name(), "TIMER0.CR");
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().span.line, 8);
+    }
+}
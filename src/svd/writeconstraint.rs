@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::elementext::ElementExt;
-use xmltree::Element;
+use xmltree::{Element, XMLNode};
 
 use crate::encode::Encode;
 use crate::error::*;
@@ -29,8 +29,13 @@ impl Parse for WriteConstraint {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<Self> {
-        if tree.children.len() == 1 {
-            let field = &tree.children[0].name;
+        let children: Vec<_> = tree
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .collect();
+        if children.len() == 1 {
+            let field = &children[0].name;
             // Write constraint can only be one of the following
             match field.as_ref() {
                 "writeAsRead" => Ok(WriteConstraint::WriteAsRead(
@@ -42,10 +47,10 @@ impl Parse for WriteConstraint {
                 "range" => Ok(WriteConstraint::Range(WriteConstraintRange::parse(
                     tree.get_child_elem(field.as_ref())?,
                 )?)),
-                _ => Err(SVDError::UnknownWriteConstraint(tree.clone()).into()),
+                _ => Err(SVDError::UnknownWriteConstraint(tree.into()).into()),
             }
         } else {
-            Err(SVDError::MoreThanOneWriteConstraint(tree.clone()).into())
+            Err(SVDError::MoreThanOneWriteConstraint(tree.into()).into())
         }
     }
 }
@@ -68,8 +73,7 @@ impl Encode for WriteConstraint {
             namespaces: None,
             name: String::from("writeConstraint"),
             attributes: HashMap::new(),
-            children: vec![v],
-            text: None,
+            children: vec![XMLNode::Element(v)],
         })
     }
 }
@@ -97,10 +101,9 @@ impl Encode for WriteConstraintRange {
             name: String::from("range"),
             attributes: HashMap::new(),
             children: vec![
-                new_element("minimum", Some(format!("0x{:08.x}", self.min))),
-                new_element("maximum", Some(format!("0x{:08.x}", self.max))),
+                XMLNode::Element(new_element("minimum", Some(format!("0x{:08.x}", self.min)))),
+                XMLNode::Element(new_element("maximum", Some(format!("0x{:08.x}", self.max)))),
             ],
-            text: None,
         })
     }
 }
@@ -112,7 +115,7 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![
+        let tests = [
             (
                 WriteConstraint::WriteAsRead(true),
                 "<writeConstraint><writeAsRead>true</writeAsRead></writeConstraint>"
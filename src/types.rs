@@ -1,6 +1,8 @@
 //! Shared primitive types for use in SVD objects.
 #![allow(clippy::manual_strip)]
 
+use core::convert::TryFrom;
+
 use xmltree::Element;
 
 pub use crate::encode::Encode;
@@ -9,19 +11,14 @@ pub use crate::parse::Parse;
 
 use crate::elementext::ElementExt;
 use crate::error::*;
-
-macro_rules! unwrap {
-    ($e:expr) => {
-        $e.expect(concat!(file!(), ":", line!(), " ", stringify!($e)))
-    };
-}
+use crate::new_element;
 
 impl Parse for u32 {
     type Object = u32;
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<u32> {
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         if text.starts_with("0x") || text.starts_with("0X") {
             u32::from_str_radix(&text["0x".len()..], 16)
@@ -53,7 +50,7 @@ impl Parse for u64 {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<u64> {
-        let text = tree.get_text()?;
+        let text = tree.get_content()?;
 
         if text.starts_with("0x") || text.starts_with("0X") {
             u64::from_str_radix(&text["0x".len()..], 16)
@@ -87,38 +84,213 @@ impl Parse for BoolParse {
     type Error = anyhow::Error;
 
     fn parse(tree: &Element) -> Result<bool> {
-        let text = unwrap!(tree.text.as_ref());
+        let text = tree.get_content()?;
         Ok(match text.as_ref() {
             "0" => false,
             "1" => true,
             _ => match text.parse() {
                 Ok(b) => b,
                 Err(e) => {
-                    return Err(SVDError::InvalidBooleanValue(tree.clone(), text.clone(), e).into())
+                    return Err(SVDError::InvalidBooleanValue(tree.into(), text.clone(), e).into())
                 }
             },
         })
     }
 }
 
-pub struct DimIndex;
-
-impl Parse for DimIndex {
-    type Object = Vec<String>;
-    type Error = anyhow::Error;
+/// Strings substituted for the `%s` placeholder of an array,
+/// kept in the form they were written in
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DimIndex {
+    /// Inclusive numeric range, e.g. `0-3`
+    NumberRange(u32, u32),
+    /// Inclusive range of uppercase letters, e.g. `A-D`
+    LetterRange(char, char),
+    /// Comma separated list, e.g. `A,B,C` or a single value
+    List(Vec<String>),
+}
 
-    fn parse(tree: &Element) -> Result<Vec<String>> {
-        let text = tree.get_text()?;
+impl DimIndex {
+    /// Parses the content of a `dimIndex` tag
+    pub fn parse_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let invalid = || DimIndexError::Invalid(text.to_string());
         if text.contains('-') {
             let mut parts = text.splitn(2, '-');
-            let start = unwrap!(unwrap!(parts.next()).parse::<u32>());
-            let end = unwrap!(unwrap!(parts.next()).parse::<u32>()) + 1;
-
-            Ok((start..end).map(|i| i.to_string()).collect())
+            let start = parts.next().unwrap_or_default().trim();
+            let end = parts.next().unwrap_or_default().trim();
+            let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+            let as_letter = |s: &str| {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+                    _ => None,
+                }
+            };
+            let (range, reversed) = if is_number(start) && is_number(end) {
+                let start = start.parse::<u32>().map_err(|_| invalid())?;
+                let end = end.parse::<u32>().map_err(|_| invalid())?;
+                (DimIndex::NumberRange(start, end), start > end)
+            } else if let (Some(start), Some(end)) = (as_letter(start), as_letter(end)) {
+                (DimIndex::LetterRange(start, end), start > end)
+            } else {
+                return Err(invalid().into());
+            };
+            if reversed {
+                return Err(DimIndexError::ReversedRange(text.to_string()).into());
+            }
+            Ok(range)
         } else {
-            Ok(text.split(',').map(|s| s.to_string()).collect())
+            let values: Vec<String> = text.split(',').map(|s| s.trim().to_string()).collect();
+            let is_valid = |s: &String| {
+                !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            };
+            if values.iter().all(is_valid) {
+                Ok(DimIndex::List(values))
+            } else {
+                Err(invalid().into())
+            }
+        }
+    }
+
+    /// Returns an iterator over the strings substituted for the `%s` placeholder,
+    /// one per array element
+    pub fn indexes(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.len()).map_while(move |i| self.get(i))
+    }
+
+    /// Returns the string substituted for the `%s` placeholder of the array element `i`
+    pub fn get(&self, i: usize) -> Option<String> {
+        match self {
+            DimIndex::NumberRange(start, end) => u32::try_from(i)
+                .ok()
+                .and_then(|i| start.checked_add(i))
+                .filter(|n| n <= end)
+                .map(|n| n.to_string()),
+            DimIndex::LetterRange(start, end) => u32::try_from(i)
+                .ok()
+                .and_then(|i| (*start as u32).checked_add(i))
+                .and_then(char::from_u32)
+                .filter(|c| c <= end)
+                .map(String::from),
+            DimIndex::List(values) => values.get(i).cloned(),
         }
     }
+
+    /// Returns the number of the array element whose `%s` placeholder is substituted by `index`
+    pub fn position(&self, index: &str) -> Option<usize> {
+        match self {
+            DimIndex::NumberRange(start, end) => index
+                .parse::<u32>()
+                .ok()
+                .filter(|n| n.to_string() == index && start <= n && n <= end)
+                .map(|n| (n - start) as usize),
+            DimIndex::LetterRange(start, end) => {
+                let mut chars = index.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if *start <= c && c <= *end => {
+                        Some(c as usize - *start as usize)
+                    }
+                    _ => None,
+                }
+            }
+            DimIndex::List(values) => values.iter().position(|v| v == index),
+        }
+    }
+
+    /// Returns the number of array elements
+    pub fn len(&self) -> usize {
+        match self {
+            DimIndex::NumberRange(start, end) if start <= end => (end - start) as usize + 1,
+            DimIndex::LetterRange(start, end) if start <= end => {
+                (*end as usize) - (*start as usize) + 1
+            }
+            DimIndex::NumberRange(..) | DimIndex::LetterRange(..) => 0,
+            DimIndex::List(values) => values.len(),
+        }
+    }
+
+    /// Returns `true` if there are no array elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl core::fmt::Display for DimIndex {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            DimIndex::NumberRange(start, end) => write!(f, "{}-{}", start, end),
+            DimIndex::LetterRange(start, end) => write!(f, "{}-{}", start, end),
+            DimIndex::List(values) => f.write_str(&values.join(",")),
+        }
+    }
+}
+
+impl Parse for DimIndex {
+    type Object = Self;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Self> {
+        Self::parse_str(&tree.get_content()?)
+    }
 }
 
-//TODO: encode for DimIndex
+impl Encode for DimIndex {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(new_element("dimIndex", Some(self.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn dim_index() {
+        let tests = [
+            (DimIndex::NumberRange(0, 31), "<dimIndex>0-31</dimIndex>"),
+            (DimIndex::LetterRange('A', 'D'), "<dimIndex>A-D</dimIndex>"),
+            (
+                DimIndex::List(vec!["10".to_string(), "20".to_string()]),
+                "<dimIndex>10,20</dimIndex>",
+            ),
+            (
+                DimIndex::List(vec!["A".to_string()]),
+                "<dimIndex>A</dimIndex>",
+            ),
+        ];
+        run_test::<DimIndex>(&tests[..]);
+
+        assert_eq!(
+            DimIndex::NumberRange(3, 5).indexes().collect::<Vec<_>>(),
+            vec!["3", "4", "5"]
+        );
+        assert_eq!(
+            DimIndex::LetterRange('A', 'C')
+                .indexes()
+                .collect::<Vec<_>>(),
+            vec!["A", "B", "C"]
+        );
+        assert_eq!(DimIndex::LetterRange('A', 'C').len(), 3);
+        assert_eq!(
+            DimIndex::parse_str("A, B,_c").unwrap(),
+            DimIndex::List(vec!["A".to_string(), "B".to_string(), "_c".to_string()])
+        );
+        for invalid in &[
+            "A-3",
+            "a-d",
+            "5-1",
+            "D-A",
+            "0-99999999999",
+            "A,,B",
+            "A B",
+            "-",
+        ] {
+            assert!(DimIndex::parse_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::panic;
+
+use rayon::prelude::*;
+use svd_parser as svd;
+use xmltree::{Element, XMLNode};
+
+/// Texts put in place of the content and attributes of elements
+const GARBAGE: [&str; 14] = [
+    "",
+    "-1",
+    "0x",
+    "0xFFFFFFFFFFFFFFFFFFFF",
+    "0xFFFFFFFFFFFFFFFF",
+    "0xFFFFFFFF",
+    "4294967295",
+    "#1x",
+    "[0:9]",
+    "%s%s",
+    "9-0",
+    ",",
+    "true",
+    "\u{e9}\u{1F600}",
+];
+
+fn sample() -> Element {
+    let xml = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/sample.svd"
+    ));
+    svd::parse(xml).expect("sample is valid");
+    Element::parse(xml.as_bytes()).unwrap()
+}
+
+/// Returns `true` if `elem` has no child elements
+fn is_leaf(elem: &Element) -> bool {
+    elem.children.iter().all(|c| c.as_element().is_none())
+}
+
+/// Paths of child indices to all elements of `tree`
+fn paths(tree: &Element) -> Vec<Vec<usize>> {
+    fn walk(elem: &Element, path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+        paths.push(path.clone());
+        for (i, c) in elem.children.iter().enumerate() {
+            if let XMLNode::Element(c) = c {
+                path.push(i);
+                walk(c, path, paths);
+                path.pop();
+            }
+        }
+    }
+    let mut paths = Vec::new();
+    walk(tree, &mut Vec::new(), &mut paths);
+    paths
+}
+
+/// Applies `f` to the parent of the element at `path` and the index of the element
+fn mutate(tree: &Element, path: &[usize], f: &dyn Fn(&mut Element, usize)) -> String {
+    let mut tree = tree.clone();
+    if let Some((last, parents)) = path.split_last() {
+        let mut parent = &mut tree;
+        for i in parents {
+            parent = parent.children[*i].as_mut_element().unwrap();
+        }
+        f(parent, *last);
+    }
+    let mut xml = Vec::new();
+    tree.write(&mut xml).unwrap();
+    String::from_utf8(xml).unwrap()
+}
+
+/// Malformed variants of the sample: every element removed, duplicated,
+/// emptied, with its content or attributes replaced by garbage, and the
+/// source truncated at every line
+fn corpus() -> Vec<String> {
+    let tree = sample();
+    let mut corpus = Vec::new();
+    for path in paths(&tree).iter().skip(1) {
+        corpus.push(mutate(&tree, path, &|p, i| {
+            p.children.remove(i);
+        }));
+        corpus.push(mutate(&tree, path, &|p, i| {
+            let copy = p.children[i].clone();
+            p.children.insert(i, copy);
+        }));
+        corpus.push(mutate(&tree, path, &|p, i| {
+            p.children[i].as_mut_element().unwrap().children.clear();
+        }));
+        corpus.push(mutate(&tree, path, &|p, i| {
+            p.children[i].as_mut_element().unwrap().name = "bogus".to_string();
+        }));
+        let elem = path
+            .iter()
+            .fold(&tree, |e, i| e.children[*i].as_element().unwrap());
+        if is_leaf(elem) || !elem.attributes.is_empty() {
+            for garbage in GARBAGE.iter() {
+                corpus.push(mutate(&tree, path, &|p, i| {
+                    let elem = p.children[i].as_mut_element().unwrap();
+                    if is_leaf(elem) {
+                        elem.children = vec![XMLNode::Text(garbage.to_string())];
+                    }
+                    for value in elem.attributes.values_mut() {
+                        *value = garbage.to_string();
+                    }
+                }));
+            }
+        }
+    }
+
+    let xml = mutate(&tree, &[], &|_, _| {});
+    corpus.extend(
+        xml.match_indices('>')
+            .map(|(i, _)| xml[..i].to_string())
+            .step_by(7),
+    );
+    corpus
+}
+
+#[test]
+fn parse_never_panics() {
+    let corpus = corpus();
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let panics: Vec<_> = corpus
+        .par_iter()
+        .filter_map(|xml| {
+            panic::catch_unwind(|| {
+                let _ = svd::parse(xml);
+                let _ = svd::parse_with_diagnostics(xml, &svd::parse::Config::default());
+            })
+            .err()
+            .map(|e| {
+                let msg = e
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or_default();
+                (msg, xml)
+            })
+        })
+        .collect();
+    panic::set_hook(hook);
+    assert!(
+        panics.is_empty(),
+        "{} of {} inputs panic, the first one with `{}`:\n{}",
+        panics.len(),
+        corpus.len(),
+        panics[0].0,
+        panics[0].1
+    );
+}